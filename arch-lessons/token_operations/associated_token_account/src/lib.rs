@@ -0,0 +1,299 @@
+// The lint policy in Cargo.toml is for on-chain code; tests unwrap and add freely
+#![cfg_attr(test, allow(clippy::unwrap_used, clippy::arithmetic_side_effects))]
+
+use arch_program::{
+    account::{next_account_info, AccountInfo, MIN_ACCOUNT_LAMPORTS},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+// ============================================================================
+// ASSOCIATED TOKEN ACCOUNTS - One Predictable Account per Wallet and Mint
+// ============================================================================
+// Companion program to the token program: it derives a token account address
+// from (wallet, token program, mint) and creates it there, so anyone can find
+// or create a wallet's account for a mint without being told its address
+
+#[cfg(not(feature = "no-entrypoint"))]
+arch_program::entrypoint!(process_instruction);
+
+/// Size of a token account in the token program's packed layout
+const TOKEN_ACCOUNT_LEN: usize = 165;
+
+/// Where a token account keeps its state byte, which is 0 until the token
+/// program initializes the account
+const TOKEN_ACCOUNT_STATE_OFFSET: usize = 108;
+
+/// The token program's `InitializeAccount` as Borsh encodes it: just the
+/// variant's index. Encoded by hand so this crate doesn't link a second copy
+/// of the token program
+const INITIALIZE_ACCOUNT: u8 = 1;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AssociatedTokenAccountInstruction {
+    /// Create and initialize the associated token account for a wallet and
+    /// mint, failing if it already exists
+    /// Accounts expected:
+    /// 0. `[writable, signer]` The funding account, paying for the new account
+    /// 1. `[writable]` The associated token account address
+    /// 2. `[]` The wallet that will own the account
+    /// 3. `[]` The token mint
+    /// 4. `[]` The system program
+    /// 5. `[]` The token program
+    Create,
+
+    /// Like `Create`, but succeeds without changes if the account already
+    /// exists with the right owner and mint, so it's safe to send every time
+    /// Accounts expected: same as `Create`
+    CreateIdempotent,
+}
+
+// ============================================================================
+// CLIENT HELPERS - Finding Addresses and Building Instructions
+// ============================================================================
+
+/// The associated token account address for `wallet` and `mint`, and the
+/// bump that makes it a program address
+pub fn get_associated_token_address_and_bump(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[&wallet.serialize(), &token_program_id.serialize(), &mint.serialize()],
+        program_id,
+    )
+}
+
+/// The associated token account address for `wallet` and `mint`
+pub fn get_associated_token_address(
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Pubkey {
+    get_associated_token_address_and_bump(wallet, mint, token_program_id, program_id).0
+}
+
+/// Builds a `Create` instruction, paid for by `funder`
+pub fn create_associated_token_account(
+    funder: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Instruction {
+    build_create_instruction(
+        AssociatedTokenAccountInstruction::Create,
+        funder,
+        wallet,
+        mint,
+        token_program_id,
+        program_id,
+    )
+}
+
+/// Builds a `CreateIdempotent` instruction, paid for by `funder`
+pub fn create_associated_token_account_idempotent(
+    funder: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Instruction {
+    build_create_instruction(
+        AssociatedTokenAccountInstruction::CreateIdempotent,
+        funder,
+        wallet,
+        mint,
+        token_program_id,
+        program_id,
+    )
+}
+
+fn build_create_instruction(
+    instruction: AssociatedTokenAccountInstruction,
+    funder: &Pubkey,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    token_program_id: &Pubkey,
+    program_id: &Pubkey,
+) -> Instruction {
+    let address = get_associated_token_address(wallet, mint, token_program_id, program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(address, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(Pubkey::system_program(), false),
+            AccountMeta::new_readonly(*token_program_id, false),
+        ],
+        // A fieldless enum always serializes to its one-byte index
+        data: vec![instruction as u8],
+    }
+}
+
+// ============================================================================
+// PROGRAM - Create the Account With Cross-Program Invocations
+// ============================================================================
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = AssociatedTokenAccountInstruction::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    match instruction {
+        AssociatedTokenAccountInstruction::Create => {
+            msg!("Instruction: Create");
+            process_create(program_id, accounts, false)
+        }
+        AssociatedTokenAccountInstruction::CreateIdempotent => {
+            msg!("Instruction: Create Idempotent");
+            process_create(program_id, accounts, true)
+        }
+    }
+}
+
+fn process_create(program_id: &Pubkey, accounts: &[AccountInfo], idempotent: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let funder_info = next_account_info(account_info_iter)?;
+    let associated_account_info = next_account_info(account_info_iter)?;
+    let wallet_info = next_account_info(account_info_iter)?;
+    let mint_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let token_program_info = next_account_info(account_info_iter)?;
+
+    // Any other program would be handed our signature for the new address
+    if *system_program_info.key != Pubkey::system_program() {
+        msg!("Expected the system program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Only the derived address may be used, or the account wouldn't be
+    // where everyone else looks for it
+    let wallet_seed = wallet_info.key.serialize();
+    let token_program_seed = token_program_info.key.serialize();
+    let mint_seed = mint_info.key.serialize();
+    let (address, bump) = Pubkey::find_program_address(
+        &[&wallet_seed, &token_program_seed, &mint_seed],
+        program_id,
+    );
+    if address != *associated_account_info.key {
+        msg!("Associated address does not match seed derivation");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if idempotent && associated_account_info.owner == token_program_info.key {
+        return check_existing_account(associated_account_info, wallet_info.key, mint_info.key);
+    }
+
+    // Say so up front rather than have the system program's failure come
+    // back as a generic cross-program invocation error
+    if *associated_account_info.owner != Pubkey::system_program() {
+        msg!("Associated token account already exists");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    // The program signs for its derived address with the seeds and bump,
+    // which makes the system program give it to the token program
+    let bump_seed = [bump];
+    let signer_seeds: &[&[u8]] = &[&wallet_seed, &token_program_seed, &mint_seed, &bump_seed];
+    let lamports = associated_account_info.lamports();
+    if lamports == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                funder_info.key,
+                associated_account_info.key,
+                MIN_ACCOUNT_LAMPORTS,
+                TOKEN_ACCOUNT_LEN as u64,
+                token_program_info.key,
+            ),
+            &[
+                funder_info.clone(),
+                associated_account_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+    } else {
+        // Anyone can send lamports to the address ahead of time, which
+        // create_account refuses, so as in SPL the account is topped up and
+        // then allocated and assigned one step at a time
+        let top_up = MIN_ACCOUNT_LAMPORTS.saturating_sub(lamports);
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(funder_info.key, associated_account_info.key, top_up),
+                &[
+                    funder_info.clone(),
+                    associated_account_info.clone(),
+                    system_program_info.clone(),
+                ],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(associated_account_info.key, TOKEN_ACCOUNT_LEN as u64),
+            &[associated_account_info.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(associated_account_info.key, token_program_info.key),
+            &[associated_account_info.clone(), system_program_info.clone()],
+            &[signer_seeds],
+        )?;
+    }
+
+    // Then the token program initializes it for the wallet; it checks the
+    // mint, so this program doesn't have to
+    invoke(
+        &Instruction {
+            program_id: *token_program_info.key,
+            accounts: vec![
+                AccountMeta::new(*associated_account_info.key, false),
+                AccountMeta::new_readonly(*mint_info.key, false),
+                AccountMeta::new_readonly(*wallet_info.key, false),
+            ],
+            data: vec![INITIALIZE_ACCOUNT],
+        },
+        &[
+            associated_account_info.clone(),
+            mint_info.clone(),
+            wallet_info.clone(),
+            token_program_info.clone(),
+        ],
+    )?;
+
+    msg!("Created associated token account {}", associated_account_info.key);
+    Ok(())
+}
+
+/// An account that already exists at the address only counts as created if
+/// the token program has initialized it for the same wallet and mint. The
+/// token account layout starts with the mint and then the owner
+fn check_existing_account(account_info: &AccountInfo, wallet: &Pubkey, mint: &Pubkey) -> ProgramResult {
+    let data = account_info.data.borrow();
+    if data.len() != TOKEN_ACCOUNT_LEN {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if data[TOKEN_ACCOUNT_STATE_OFFSET] == 0 {
+        msg!("Existing account is not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if Pubkey::from_slice(&data[0..32]) != *mint || Pubkey::from_slice(&data[32..64]) != *wallet {
+        msg!("Existing account does not belong to this wallet and mint");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    msg!("Associated token account already exists");
+    Ok(())
+}
@@ -1,10 +1,11 @@
 use arch_program::{
-    account::AccountInfo,
+    account::{next_account_info, AccountInfo},
     entrypoint,
     entrypoint::ProgramResult,
     msg,
     program::invoke,
     program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
     system_instruction,
 };
@@ -40,13 +41,17 @@ pub enum TokenInstruction {
     /// 0. `[writable]` The mint account
     /// 1. `[writable]` The destination token account
     /// 2. `[signer]` The mint authority
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     MintTo { amount: u64 },
     
-    /// Transfer tokens between accounts
+    /// Transfer tokens between accounts. As in SPL, a transfer from an
+    /// account to itself is checked like any other but changes nothing, not
+    /// even a delegate's remaining allowance
     /// Accounts expected:
     /// 0. `[writable]` The source token account
     /// 1. `[writable]` The destination token account
-    /// 2. `[signer]` The owner of the source account
+    /// 2. `[signer]` The owner or delegate of the source account
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     Transfer { amount: u64 },
     
     /// Approve a delegate to spend tokens
@@ -54,12 +59,14 @@ pub enum TokenInstruction {
     /// 0. `[writable]` The token account to approve from
     /// 1. `[]` The delegate account
     /// 2. `[signer]` The owner of the token account
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     Approve { amount: u64 },
     
     /// Revoke a previously approved delegate
     /// Accounts expected:
     /// 0. `[writable]` The token account
     /// 1. `[signer]` The owner of the token account
+    /// 2.. `[signer]` The multisig's signers, if the authority is a multisig
     Revoke,
     
     /// Burn tokens from an account
@@ -67,6 +74,7 @@ pub enum TokenInstruction {
     /// 0. `[writable]` The token account to burn from
     /// 1. `[writable]` The mint account
     /// 2. `[signer]` The owner of the token account
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     Burn { amount: u64 },
     
     /// Freeze a token account
@@ -74,6 +82,7 @@ pub enum TokenInstruction {
     /// 0. `[writable]` The token account to freeze
     /// 1. `[]` The mint account
     /// 2. `[signer]` The freeze authority
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     FreezeAccount,
     
     /// Thaw a frozen token account
@@ -81,10 +90,193 @@ pub enum TokenInstruction {
     /// 0. `[writable]` The token account to thaw
     /// 1. `[]` The mint account
     /// 2. `[signer]` The freeze authority
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
     ThawAccount,
+    
+    /// Change or remove one of a mint's or token account's authorities.
+    /// Removing the mint authority permanently fixes the supply
+    /// Accounts expected:
+    /// 0. `[writable]` The mint or token account to change
+    /// 1. `[signer]` The current authority
+    /// 2.. `[signer]` The multisig's signers, if the authority is a multisig
+    SetAuthority {
+        authority_type: AuthorityType,
+        new_authority: Option<Pubkey>,
+    },
+    
+    /// Close an empty token account, sending its lamports to the destination
+    /// Accounts expected:
+    /// 0. `[writable]` The token account to close
+    /// 1. `[writable]` The destination for the account's lamports
+    /// 2. `[signer]` The close authority, or the owner if none is set
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
+    CloseAccount,
+    
+    /// Like `Transfer`, but fails unless `decimals` matches the mint's, so a
+    /// wallet can't send 1000x what it meant to
+    /// Accounts expected:
+    /// 0. `[writable]` The source token account
+    /// 1. `[]` The mint account
+    /// 2. `[writable]` The destination token account
+    /// 3. `[signer]` The owner or delegate of the source account
+    /// 4.. `[signer]` The multisig's signers, if the authority is a multisig
+    TransferChecked { amount: u64, decimals: u8 },
+    
+    /// Like `MintTo`, but fails unless `decimals` matches the mint's
+    /// Accounts expected:
+    /// 0. `[writable]` The mint account
+    /// 1. `[writable]` The destination token account
+    /// 2. `[signer]` The mint authority
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
+    MintToChecked { amount: u64, decimals: u8 },
+    
+    /// Like `Burn`, but fails unless `decimals` matches the mint's
+    /// Accounts expected:
+    /// 0. `[writable]` The token account to burn from
+    /// 1. `[writable]` The mint account
+    /// 2. `[signer]` The owner of the token account
+    /// 3.. `[signer]` The multisig's signers, if the authority is a multisig
+    BurnChecked { amount: u64, decimals: u8 },
+    
+    /// Like `Approve`, but fails unless `decimals` matches the mint's
+    /// Accounts expected:
+    /// 0. `[writable]` The token account to approve from
+    /// 1. `[]` The mint account
+    /// 2. `[]` The delegate account
+    /// 3. `[signer]` The owner of the token account
+    /// 4.. `[signer]` The multisig's signers, if the authority is a multisig
+    ApproveChecked { amount: u64, decimals: u8 },
+    
+    /// Initialize an m-of-n multisig that can act as any authority
+    /// Accounts expected:
+    /// 0. `[writable]` The multisig account to initialize
+    /// 1..1+N. `[]` The signer accounts, 1 to 11 of them
+    InitializeMultisig { m: u8 },
+}
+
+/// Which authority `SetAuthority` changes
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq)]
+pub enum AuthorityType {
+    /// Mint: who can mint new tokens
+    MintTokens,
+    /// Mint: who can freeze and thaw token accounts
+    FreezeAccount,
+    /// Token account: who owns the tokens
+    AccountOwner,
+    /// Token account: who can close it (the owner when unset)
+    CloseAccount,
+}
+
+/// Errors specific to the token program, returned as
+/// `ProgramError::Custom(code)` where `code` is the variant's index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenError {
+    /// Lamport balance below rent-exempt threshold
+    NotRentExempt,
+    /// Insufficient token balance
+    InsufficientFunds,
+    /// Invalid mint
+    InvalidMint,
+    /// Account not associated with this mint
+    MintMismatch,
+    /// Owner does not match
+    OwnerMismatch,
+    /// This token's supply is fixed and new tokens cannot be minted
+    FixedSupply,
+    /// The account cannot be initialized because it is already being used
+    AlreadyInitialized,
+    /// Invalid number of provided signers
+    InvalidNumberOfProvidedSigners,
+    /// Invalid number of required signers
+    InvalidNumberOfRequiredSigners,
+    /// State is uninitialized
+    UninitializedState,
+    /// Instruction does not support native tokens
+    NativeNotSupported,
+    /// Non-native account can only be closed if its balance is zero
+    NonNativeHasBalance,
+    /// Invalid instruction
+    InvalidInstruction,
+    /// State is invalid for requested operation
+    InvalidState,
+    /// Operation overflowed
+    Overflow,
+    /// Account does not support specified authority type
+    AuthorityTypeNotSupported,
+    /// This token mint cannot freeze accounts
+    MintCannotFreeze,
+    /// Account is frozen; all account operations will fail
+    AccountFrozen,
+    /// Mint decimals mismatch between the client and mint
+    MintDecimalsMismatch,
+    /// Delegate is not approved for this many tokens
+    InsufficientDelegation,
+}
+
+impl TokenError {
+    const ALL: [TokenError; 20] = [
+        TokenError::NotRentExempt,
+        TokenError::InsufficientFunds,
+        TokenError::InvalidMint,
+        TokenError::MintMismatch,
+        TokenError::OwnerMismatch,
+        TokenError::FixedSupply,
+        TokenError::AlreadyInitialized,
+        TokenError::InvalidNumberOfProvidedSigners,
+        TokenError::InvalidNumberOfRequiredSigners,
+        TokenError::UninitializedState,
+        TokenError::NativeNotSupported,
+        TokenError::NonNativeHasBalance,
+        TokenError::InvalidInstruction,
+        TokenError::InvalidState,
+        TokenError::Overflow,
+        TokenError::AuthorityTypeNotSupported,
+        TokenError::MintCannotFreeze,
+        TokenError::AccountFrozen,
+        TokenError::MintDecimalsMismatch,
+        TokenError::InsufficientDelegation,
+    ];
+    
+    /// Decodes the code carried by `ProgramError::Custom`
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+    
+    pub fn message(&self) -> &'static str {
+        match self {
+            TokenError::NotRentExempt => "Lamport balance below rent-exempt threshold",
+            TokenError::InsufficientFunds => "Insufficient funds",
+            TokenError::InvalidMint => "Invalid Mint",
+            TokenError::MintMismatch => "Account not associated with this Mint",
+            TokenError::OwnerMismatch => "Owner does not match",
+            TokenError::FixedSupply => "Fixed supply",
+            TokenError::AlreadyInitialized => "Already in use",
+            TokenError::InvalidNumberOfProvidedSigners => "Invalid number of provided signers",
+            TokenError::InvalidNumberOfRequiredSigners => "Invalid number of required signers",
+            TokenError::UninitializedState => "State is uninitialized",
+            TokenError::NativeNotSupported => "Instruction does not support native tokens",
+            TokenError::NonNativeHasBalance => "Non-native account can only be closed if its balance is zero",
+            TokenError::InvalidInstruction => "Invalid instruction",
+            TokenError::InvalidState => "State is invalid for requested operation",
+            TokenError::Overflow => "Operation overflowed",
+            TokenError::AuthorityTypeNotSupported => "Account does not support specified authority type",
+            TokenError::MintCannotFreeze => "This token mint cannot freeze accounts",
+            TokenError::AccountFrozen => "Account is frozen",
+            TokenError::MintDecimalsMismatch => "The provided decimals value different from the Mint decimals",
+            TokenError::InsufficientDelegation => "Delegate is not approved for this many tokens",
+        }
+    }
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+impl From<TokenError> for ProgramError {
+    fn from(error: TokenError) -> Self {
+        ProgramError::Custom(error as u32)
+    }
+}
+
+/// Stored with a fixed 82-byte layout (see `Pack` below), byte-compatible
+/// with SPL and APL token mints so existing token tooling can read it
+#[derive(Debug, Clone, PartialEq)]
 pub struct Mint {
     pub mint_authority: Option<Pubkey>,
     pub supply: u64,
@@ -93,7 +285,9 @@ pub struct Mint {
     pub freeze_authority: Option<Pubkey>,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+/// Stored with a fixed 165-byte layout (see `Pack` below), byte-compatible
+/// with SPL and APL token accounts
+#[derive(Debug, Clone, PartialEq)]
 pub struct TokenAccount {
     pub mint: Pubkey,
     pub owner: Pubkey,
@@ -105,83 +299,324 @@ pub struct TokenAccount {
     pub close_authority: Option<Pubkey>,
 }
 
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+/// Most signers a multisig can have
+pub const MAX_SIGNERS: usize = 11;
+
+/// An m-of-n authority: wherever a mint, freeze or owner authority is
+/// expected, a multisig's address can be used instead, and the instruction
+/// then needs `m` of its `n` signers to sign
+#[derive(Debug, Clone, PartialEq)]
+pub struct Multisig {
+    /// Signatures required
+    pub m: u8,
+    /// Valid signers; only the first `n` of `signers` are used
+    pub n: u8,
+    pub is_initialized: bool,
+    pub signers: [Pubkey; MAX_SIGNERS],
+}
+
+/// Stored as a single byte: the variant's index
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum AccountState {
     Uninitialized,
     Initialized,
     Frozen,
 }
 
-impl Mint {
-    pub const LEN: usize = 82; // Size of mint account data
+impl TokenAccount {
+    /// Frozen accounts reject every instruction except `ThawAccount`
+    pub fn is_frozen(&self) -> bool {
+        self.state == AccountState::Frozen
+    }
 }
 
-impl TokenAccount {
-    pub const LEN: usize = 165; // Size of token account data
+// ============================================================================
+// ACCOUNT LAYOUTS - Fixed-Size Packing
+// ============================================================================
+// Borsh writes `None` as 1 byte and `Some(key)` as 33, so an account's size
+// would depend on its contents. `Pack` gives every field a fixed offset
+// instead, and optional fields use the `COption` encoding: a 4-byte
+// little-endian tag (0 = None, 1 = Some) followed by the value, zeroed when
+// absent
+
+impl Sealed for Mint {}
+
+impl IsInitialized for Mint {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Mint {
+    /// mint_authority (36) | supply (8) | decimals (1) | is_initialized (1) |
+    /// freeze_authority (36)
+    const LEN: usize = 82;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        pack_coption_key(&self.mint_authority, &mut dst[0..36]);
+        dst[36..44].copy_from_slice(&self.supply.to_le_bytes());
+        dst[44] = self.decimals;
+        dst[45] = self.is_initialized as u8;
+        pack_coption_key(&self.freeze_authority, &mut dst[46..82]);
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Mint {
+            mint_authority: unpack_coption_key(&src[0..36])?,
+            supply: unpack_u64(&src[36..44])?,
+            decimals: src[44],
+            is_initialized: unpack_bool(src[45])?,
+            freeze_authority: unpack_coption_key(&src[46..82])?,
+        })
+    }
+}
+
+impl Sealed for TokenAccount {}
+
+impl IsInitialized for TokenAccount {
+    fn is_initialized(&self) -> bool {
+        self.state != AccountState::Uninitialized
+    }
 }
 
-fn process_instruction(
+impl Pack for TokenAccount {
+    /// mint (32) | owner (32) | amount (8) | delegate (36) | state (1) |
+    /// is_native (12) | delegated_amount (8) | close_authority (36)
+    const LEN: usize = 165;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0..32].copy_from_slice(&self.mint.serialize());
+        dst[32..64].copy_from_slice(&self.owner.serialize());
+        dst[64..72].copy_from_slice(&self.amount.to_le_bytes());
+        pack_coption_key(&self.delegate, &mut dst[72..108]);
+        dst[108] = self.state as u8;
+        pack_coption_u64(&self.is_native, &mut dst[109..121]);
+        dst[121..129].copy_from_slice(&self.delegated_amount.to_le_bytes());
+        pack_coption_key(&self.close_authority, &mut dst[129..165]);
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let state = match src[108] {
+            0 => AccountState::Uninitialized,
+            1 => AccountState::Initialized,
+            2 => AccountState::Frozen,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        Ok(TokenAccount {
+            mint: Pubkey::from_slice(&src[0..32]),
+            owner: Pubkey::from_slice(&src[32..64]),
+            amount: unpack_u64(&src[64..72])?,
+            delegate: unpack_coption_key(&src[72..108])?,
+            state,
+            is_native: unpack_coption_u64(&src[109..121])?,
+            delegated_amount: unpack_u64(&src[121..129])?,
+            close_authority: unpack_coption_key(&src[129..165])?,
+        })
+    }
+}
+
+impl Sealed for Multisig {}
+
+impl IsInitialized for Multisig {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Multisig {
+    /// m (1) | n (1) | is_initialized (1) | signers (11 x 32)
+    const LEN: usize = 355;
+    
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.m;
+        dst[1] = self.n;
+        dst[2] = self.is_initialized as u8;
+        for (slot, signer) in dst[3..355].chunks_exact_mut(32).zip(&self.signers) {
+            slot.copy_from_slice(&signer.serialize());
+        }
+    }
+    
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut signers = [Pubkey::from_slice(&[0; 32]); MAX_SIGNERS];
+        for (signer, slot) in signers.iter_mut().zip(src[3..355].chunks_exact(32)) {
+            *signer = Pubkey::from_slice(slot);
+        }
+        Ok(Multisig {
+            m: src[0],
+            n: src[1],
+            is_initialized: unpack_bool(src[2])?,
+            signers,
+        })
+    }
+}
+
+fn pack_coption_key(src: &Option<Pubkey>, dst: &mut [u8]) {
+    let (tag, body) = dst.split_at_mut(4);
+    match src {
+        Some(key) => {
+            tag.copy_from_slice(&[1, 0, 0, 0]);
+            body.copy_from_slice(&key.serialize());
+        }
+        None => {
+            tag.copy_from_slice(&[0; 4]);
+            body.fill(0);
+        }
+    }
+}
+
+fn unpack_coption_key(src: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    let (tag, body) = src.split_at(4);
+    match tag {
+        [0, 0, 0, 0] => Ok(None),
+        [1, 0, 0, 0] => Ok(Some(Pubkey::from_slice(body))),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn pack_coption_u64(src: &Option<u64>, dst: &mut [u8]) {
+    let (tag, body) = dst.split_at_mut(4);
+    match src {
+        Some(value) => {
+            tag.copy_from_slice(&[1, 0, 0, 0]);
+            body.copy_from_slice(&value.to_le_bytes());
+        }
+        None => {
+            tag.copy_from_slice(&[0; 4]);
+            body.fill(0);
+        }
+    }
+}
+
+fn unpack_coption_u64(src: &[u8]) -> Result<Option<u64>, ProgramError> {
+    let (tag, body) = src.split_at(4);
+    match tag {
+        [0, 0, 0, 0] => Ok(None),
+        [1, 0, 0, 0] => unpack_u64(body).map(Some),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+fn unpack_u64(src: &[u8]) -> Result<u64, ProgramError> {
+    src.try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+fn unpack_bool(src: u8) -> Result<bool, ProgramError> {
+    match src {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-    let instruction = TokenInstruction::try_from_slice(instruction_data)?;
+    let instruction = TokenInstruction::try_from_slice(instruction_data)
+        .map_err(|_| ProgramError::from(TokenError::InvalidInstruction))?;
     
-    match instruction {
+    let result = match instruction {
         TokenInstruction::InitializeMint { decimals } => {
             msg!("Instruction: Initialize Mint");
-            process_initialize_mint(accounts, decimals)
+            process_initialize_mint(program_id, accounts, decimals)
         }
         TokenInstruction::InitializeAccount => {
             msg!("Instruction: Initialize Token Account");
-            process_initialize_account(accounts)
+            process_initialize_account(program_id, accounts)
         }
         TokenInstruction::MintTo { amount } => {
             msg!("Instruction: Mint To");
-            process_mint_to(accounts, amount)
+            process_mint_to(program_id, accounts, amount, None)
         }
         TokenInstruction::Transfer { amount } => {
             msg!("Instruction: Transfer");
-            process_transfer(accounts, amount)
+            process_transfer(program_id, accounts, amount, None)
         }
         TokenInstruction::Approve { amount } => {
             msg!("Instruction: Approve");
-            process_approve(accounts, amount)
+            process_approve(program_id, accounts, amount, None)
         }
         TokenInstruction::Revoke => {
             msg!("Instruction: Revoke");
-            process_revoke(accounts)
+            process_revoke(program_id, accounts)
         }
         TokenInstruction::Burn { amount } => {
             msg!("Instruction: Burn");
-            process_burn(accounts, amount)
+            process_burn(program_id, accounts, amount, None)
         }
         TokenInstruction::FreezeAccount => {
             msg!("Instruction: Freeze Account");
-            process_freeze_account(accounts)
+            process_freeze_account(program_id, accounts)
         }
         TokenInstruction::ThawAccount => {
             msg!("Instruction: Thaw Account");
-            process_thaw_account(accounts)
+            process_thaw_account(program_id, accounts)
+        }
+        TokenInstruction::SetAuthority { authority_type, new_authority } => {
+            msg!("Instruction: Set Authority");
+            process_set_authority(program_id, accounts, authority_type, new_authority)
+        }
+        TokenInstruction::CloseAccount => {
+            msg!("Instruction: Close Account");
+            process_close_account(program_id, accounts)
+        }
+        TokenInstruction::TransferChecked { amount, decimals } => {
+            msg!("Instruction: Transfer Checked");
+            process_transfer(program_id, accounts, amount, Some(decimals))
+        }
+        TokenInstruction::MintToChecked { amount, decimals } => {
+            msg!("Instruction: Mint To Checked");
+            process_mint_to(program_id, accounts, amount, Some(decimals))
+        }
+        TokenInstruction::BurnChecked { amount, decimals } => {
+            msg!("Instruction: Burn Checked");
+            process_burn(program_id, accounts, amount, Some(decimals))
+        }
+        TokenInstruction::ApproveChecked { amount, decimals } => {
+            msg!("Instruction: Approve Checked");
+            process_approve(program_id, accounts, amount, Some(decimals))
+        }
+        TokenInstruction::InitializeMultisig { m } => {
+            msg!("Instruction: Initialize Multisig");
+            process_initialize_multisig(program_id, accounts, m)
+        }
+    };
+    
+    // Custom codes are just numbers in the transaction logs, so spell out
+    // which token error it was
+    if let Err(ProgramError::Custom(code)) = &result {
+        if let Some(error) = TokenError::from_code(*code) {
+            msg!("Error: {}", error.message());
         }
     }
+    result
 }
 
-fn process_initialize_mint(accounts: &[AccountInfo], decimals: u8) -> ProgramResult {
+fn process_initialize_mint(program_id: &Pubkey, accounts: &[AccountInfo], decimals: u8) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let mint_info = next_account_info(account_info_iter)?;
     let mint_authority_info = next_account_info(account_info_iter)?;
     let freeze_authority_info = account_info_iter.next();
     
-    // TODO: Add validation checks
-    // - Verify mint account is writable
-    // - Check that mint account is not already initialized
-    // - Validate decimals (typically 0-9)
+    // Any decimals value is accepted, as in SPL; most tokens use 0-9
+    
+    check_program_account(program_id, mint_info)?;
+    check_writable(mint_info)?;
     
     if mint_info.data_len() != Mint::LEN {
         return Err(ProgramError::InvalidAccountData);
     }
     
+    // Without this anyone could reinitialize an existing mint and make
+    // themselves its authority
+    let existing = Mint::unpack_unchecked(&mint_info.data.borrow())?;
+    if existing.is_initialized() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    
     let freeze_authority = freeze_authority_info.map(|info| *info.key);
     
     let mint = Mint {
@@ -192,33 +627,37 @@ fn process_initialize_mint(accounts: &[AccountInfo], decimals: u8) -> ProgramRes
         freeze_authority,
     };
     
-    // TODO: Serialize and store mint data
-    let mint_data = mint.try_to_vec()?;
-    mint_info.data.borrow_mut()[..mint_data.len()].copy_from_slice(&mint_data);
+    // Store the packed mint
+    Mint::pack(mint, &mut mint_info.data.borrow_mut())?;
     
     msg!("Mint initialized with {} decimals", decimals);
     Ok(())
 }
 
-fn process_initialize_account(accounts: &[AccountInfo]) -> ProgramResult {
+fn process_initialize_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
     
-    // TODO: Add validation checks
-    // - Verify token account is writable
-    // - Check that token account is not already initialized
-    // - Validate that mint account exists and is initialized
+    check_program_account(program_id, token_account_info)?;
+    check_writable(token_account_info)?;
     
     if token_account_info.data_len() != TokenAccount::LEN {
         return Err(ProgramError::InvalidAccountData);
     }
     
-    // TODO: Verify mint account is valid
-    let mint_data = Mint::try_from_slice(&mint_info.data.borrow())?;
-    if !mint_data.is_initialized {
-        return Err(ProgramError::UninitializedAccount);
+    // Reinitializing would hand the balance to a new owner
+    let existing = TokenAccount::unpack_unchecked(&token_account_info.data.borrow())?;
+    if existing.is_initialized() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    
+    // The mint must be a real mint of this program, not look-alike data
+    check_program_account(program_id, mint_info)?;
+    let mint_data = Mint::unpack_unchecked(&mint_info.data.borrow())?;
+    if !mint_data.is_initialized() {
+        return Err(TokenError::InvalidMint.into());
     }
     
     let token_account = TokenAccount {
@@ -232,19 +671,24 @@ fn process_initialize_account(accounts: &[AccountInfo]) -> ProgramResult {
         close_authority: None,
     };
     
-    // TODO: Serialize and store token account data
-    let account_data = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..account_data.len()].copy_from_slice(&account_data);
+    // Store the packed token account
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
     msg!("Token account initialized for mint: {}", mint_info.key);
     Ok(())
 }
 
-fn process_mint_to(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+fn process_mint_to(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let mint_info = next_account_info(account_info_iter)?;
     let destination_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement mint_to logic
     // 1. Load and validate mint account
@@ -253,40 +697,60 @@ fn process_mint_to(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
     // 4. Update mint supply
     // 5. Update destination account balance
     
-    let mut mint_data = Mint::try_from_slice(&mint_info.data.borrow())?;
-    let mut dest_account = TokenAccount::try_from_slice(&destination_info.data.borrow())?;
+    check_writable(mint_info)?;
+    check_writable(destination_info)?;
+    let mut mint_data = load_mint(program_id, mint_info)?;
+    let mut dest_account = load_token_account(program_id, destination_info)?;
     
-    // Validate authority
-    if mint_data.mint_authority != Some(*authority_info.key) {
-        return Err(ProgramError::InvalidArgument);
+    // Otherwise a worthless mint's authority could mint into accounts of
+    // any other mint
+    if dest_account.mint != *mint_info.key {
+        return Err(TokenError::MintMismatch.into());
     }
     
-    if !authority_info.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+    if let Some(expected_decimals) = expected_decimals {
+        check_decimals(&mint_data, expected_decimals)?;
+    }
+    
+    if dest_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
     }
     
+    // Validate authority; a mint without one can never mint again
+    let mint_authority = mint_data.mint_authority.ok_or(TokenError::FixedSupply)?;
+    validate_owner(program_id, &mint_authority, authority_info, signers)?;
+    
     // TODO: Add overflow checks
     mint_data.supply = mint_data.supply.checked_add(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+        .ok_or(TokenError::Overflow)?;
     dest_account.amount = dest_account.amount.checked_add(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+        .ok_or(TokenError::Overflow)?;
     
     // Save updated data
-    let mint_serialized = mint_data.try_to_vec()?;
-    mint_info.data.borrow_mut()[..mint_serialized.len()].copy_from_slice(&mint_serialized);
+    Mint::pack(mint_data, &mut mint_info.data.borrow_mut())?;
     
-    let dest_serialized = dest_account.try_to_vec()?;
-    destination_info.data.borrow_mut()[..dest_serialized.len()].copy_from_slice(&dest_serialized);
+    TokenAccount::pack(dest_account, &mut destination_info.data.borrow_mut())?;
     
     msg!("Minted {} tokens to account", amount);
     Ok(())
 }
 
-fn process_transfer(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+fn process_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let source_info = next_account_info(account_info_iter)?;
+    // The checked variant passes the mint between source and destination
+    let mint_info = match expected_decimals {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
     let destination_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement transfer logic
     // 1. Load source and destination accounts
@@ -295,197 +759,520 @@ fn process_transfer(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
     // 4. Handle delegate logic if applicable
     // 5. Update balances
     
-    let mut source_account = TokenAccount::try_from_slice(&source_info.data.borrow())?;
-    let mut dest_account = TokenAccount::try_from_slice(&destination_info.data.borrow())?;
+    check_writable(source_info)?;
+    check_writable(destination_info)?;
+    let mut source_account = load_token_account(program_id, source_info)?;
+    let mut dest_account = load_token_account(program_id, destination_info)?;
+    
+    // Tokens can neither leave nor enter a frozen account
+    if source_account.is_frozen() || dest_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
+    }
     
     // Validate accounts are from same mint
     if source_account.mint != dest_account.mint {
-        return Err(ProgramError::InvalidArgument);
+        return Err(TokenError::MintMismatch.into());
     }
     
-    // Check authority
-    let is_owner = source_account.owner == *authority_info.key;
-    let is_delegate = source_account.delegate == Some(*authority_info.key);
-    
-    if !is_owner && !is_delegate {
-        return Err(ProgramError::InvalidArgument);
+    if let (Some(mint_info), Some(expected_decimals)) = (mint_info, expected_decimals) {
+        if source_account.mint != *mint_info.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        check_decimals(&load_mint(program_id, mint_info)?, expected_decimals)?;
     }
     
-    if !authority_info.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    // Check authority: an approved delegate, otherwise the owner
+    let is_delegate = source_account.delegate == Some(*authority_info.key);
+    let authority = if is_delegate { *authority_info.key } else { source_account.owner };
+    validate_owner(program_id, &authority, authority_info, signers)?;
     
     // TODO: Implement balance checks and updates
     if source_account.amount < amount {
-        return Err(ProgramError::InsufficientFunds);
+        return Err(TokenError::InsufficientFunds.into());
     }
     
-    source_account.amount = source_account.amount.checked_sub(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    dest_account.amount = dest_account.amount.checked_add(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
-    
-    // Handle delegate spending
+    // A self-transfer moves nothing, so it has to fit the delegate's
+    // allowance without using any of it up
+    let self_transfer = source_info.key == destination_info.key;
     if is_delegate {
-        if source_account.delegated_amount < amount {
-            return Err(ProgramError::InsufficientFunds);
+        if self_transfer {
+            if source_account.delegated_amount < amount {
+                return Err(TokenError::InsufficientDelegation.into());
+            }
+        } else {
+            spend_delegation(&mut source_account, amount)?;
         }
-        source_account.delegated_amount = source_account.delegated_amount.checked_sub(amount)
-            .ok_or(ProgramError::ArithmeticOverflow)?;
     }
     
+    // Both copies would be written back to the same account, the second
+    // one undoing the debit, so a checked self-transfer is already done
+    if self_transfer {
+        msg!("Transferred {} tokens", amount);
+        return Ok(());
+    }
+    
+    source_account.amount = source_account.amount.checked_sub(amount)
+        .ok_or(TokenError::Overflow)?;
+    dest_account.amount = dest_account.amount.checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    
     // Save updated accounts
-    let source_serialized = source_account.try_to_vec()?;
-    source_info.data.borrow_mut()[..source_serialized.len()].copy_from_slice(&source_serialized);
+    TokenAccount::pack(source_account, &mut source_info.data.borrow_mut())?;
     
-    let dest_serialized = dest_account.try_to_vec()?;
-    destination_info.data.borrow_mut()[..dest_serialized.len()].copy_from_slice(&dest_serialized);
+    TokenAccount::pack(dest_account, &mut destination_info.data.borrow_mut())?;
     
     msg!("Transferred {} tokens", amount);
     Ok(())
 }
 
-fn process_approve(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+fn process_approve(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
+    // The checked variant passes the mint before the delegate
+    let mint_info = match expected_decimals {
+        Some(_) => Some(next_account_info(account_info_iter)?),
+        None => None,
+    };
     let delegate_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement approve logic
     // 1. Load token account
     // 2. Validate owner signature
     // 3. Set delegate and delegated amount
     
-    let mut token_account = TokenAccount::try_from_slice(&token_account_info.data.borrow())?;
+    check_writable(token_account_info)?;
+    let mut token_account = load_token_account(program_id, token_account_info)?;
     
-    if token_account.owner != *owner_info.key {
-        return Err(ProgramError::InvalidArgument);
+    if token_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
     }
     
-    if !owner_info.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+    if let (Some(mint_info), Some(expected_decimals)) = (mint_info, expected_decimals) {
+        if token_account.mint != *mint_info.key {
+            return Err(TokenError::MintMismatch.into());
+        }
+        check_decimals(&load_mint(program_id, mint_info)?, expected_decimals)?;
     }
     
+    validate_owner(program_id, &token_account.owner, owner_info, signers)?;
+    
     token_account.delegate = Some(*delegate_info.key);
     token_account.delegated_amount = amount;
     
-    let serialized = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..serialized.len()].copy_from_slice(&serialized);
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
     msg!("Approved {} tokens for delegate", amount);
     Ok(())
 }
 
-fn process_revoke(accounts: &[AccountInfo]) -> ProgramResult {
+fn process_revoke(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
     let owner_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement revoke logic
-    let mut token_account = TokenAccount::try_from_slice(&token_account_info.data.borrow())?;
+    check_writable(token_account_info)?;
+    let mut token_account = load_token_account(program_id, token_account_info)?;
     
-    if token_account.owner != *owner_info.key {
-        return Err(ProgramError::InvalidArgument);
+    if token_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
     }
     
-    if !owner_info.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    validate_owner(program_id, &token_account.owner, owner_info, signers)?;
     
     token_account.delegate = None;
     token_account.delegated_amount = 0;
     
-    let serialized = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..serialized.len()].copy_from_slice(&serialized);
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
     msg!("Revoked delegate approval");
     Ok(())
 }
 
-fn process_burn(accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+fn process_burn(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    expected_decimals: Option<u8>,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
-    // TODO: Implement burn logic
     // Similar to transfer but removes tokens from circulation
     
-    let mut token_account = TokenAccount::try_from_slice(&token_account_info.data.borrow())?;
-    let mut mint_data = Mint::try_from_slice(&mint_info.data.borrow())?;
+    check_writable(token_account_info)?;
+    check_writable(mint_info)?;
+    let mut token_account = load_token_account(program_id, token_account_info)?;
+    let mut mint_data = load_mint(program_id, mint_info)?;
     
-    // Validate authority and balance
-    if token_account.owner != *authority_info.key {
-        return Err(ProgramError::InvalidArgument);
+    // Burning against another mint would shrink the wrong supply
+    if token_account.mint != *mint_info.key {
+        return Err(TokenError::MintMismatch.into());
+    }
+    
+    if let Some(expected_decimals) = expected_decimals {
+        check_decimals(&mint_data, expected_decimals)?;
     }
     
+    if token_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    
+    // Validate authority (owner or delegate) and balance
+    let is_delegate = token_account.delegate == Some(*authority_info.key);
+    let authority = if is_delegate { *authority_info.key } else { token_account.owner };
+    validate_owner(program_id, &authority, authority_info, signers)?;
+    
     if token_account.amount < amount {
-        return Err(ProgramError::InsufficientFunds);
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    
+    if is_delegate {
+        spend_delegation(&mut token_account, amount)?;
     }
     
     // Update balances
     token_account.amount = token_account.amount.checked_sub(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+        .ok_or(TokenError::Overflow)?;
     mint_data.supply = mint_data.supply.checked_sub(amount)
-        .ok_or(ProgramError::ArithmeticOverflow)?;
+        .ok_or(TokenError::Overflow)?;
     
     // Save data
-    let token_serialized = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..token_serialized.len()].copy_from_slice(&token_serialized);
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
-    let mint_serialized = mint_data.try_to_vec()?;
-    mint_info.data.borrow_mut()[..mint_serialized.len()].copy_from_slice(&mint_serialized);
+    Mint::pack(mint_data, &mut mint_info.data.borrow_mut())?;
     
     msg!("Burned {} tokens", amount);
     Ok(())
 }
 
-fn process_freeze_account(accounts: &[AccountInfo]) -> ProgramResult {
+fn process_freeze_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let freeze_authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement freeze logic
-    let mut token_account = TokenAccount::try_from_slice(&token_account_info.data.borrow())?;
-    let mint_data = Mint::try_from_slice(&mint_info.data.borrow())?;
+    check_writable(token_account_info)?;
+    let mut token_account = load_token_account(program_id, token_account_info)?;
+    let mint_data = load_mint(program_id, mint_info)?;
+    
+    // Only the account's own mint's freeze authority may freeze it
+    if token_account.mint != *mint_info.key {
+        return Err(TokenError::MintMismatch.into());
+    }
     
     // Validate freeze authority
-    if mint_data.freeze_authority != Some(*freeze_authority_info.key) {
-        return Err(ProgramError::InvalidArgument);
+    let freeze_authority = mint_data.freeze_authority.ok_or(TokenError::MintCannotFreeze)?;
+    validate_owner(program_id, &freeze_authority, freeze_authority_info, signers)?;
+    
+    if token_account.is_frozen() {
+        return Err(TokenError::InvalidState.into());
     }
     
     token_account.state = AccountState::Frozen;
     
-    let serialized = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..serialized.len()].copy_from_slice(&serialized);
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
     msg!("Account frozen");
     Ok(())
 }
 
-fn process_thaw_account(accounts: &[AccountInfo]) -> ProgramResult {
+fn process_thaw_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_account_info = next_account_info(account_info_iter)?;
     let mint_info = next_account_info(account_info_iter)?;
     let freeze_authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
     
     // TODO: Implement thaw logic
-    let mut token_account = TokenAccount::try_from_slice(&token_account_info.data.borrow())?;
-    let mint_data = Mint::try_from_slice(&mint_info.data.borrow())?;
+    check_writable(token_account_info)?;
+    let mut token_account = load_token_account(program_id, token_account_info)?;
+    let mint_data = load_mint(program_id, mint_info)?;
+    
+    // Only the account's own mint's freeze authority may thaw it
+    if token_account.mint != *mint_info.key {
+        return Err(TokenError::MintMismatch.into());
+    }
     
     // Validate freeze authority
-    if mint_data.freeze_authority != Some(*freeze_authority_info.key) {
-        return Err(ProgramError::InvalidArgument);
+    let freeze_authority = mint_data.freeze_authority.ok_or(TokenError::MintCannotFreeze)?;
+    validate_owner(program_id, &freeze_authority, freeze_authority_info, signers)?;
+    
+    if !token_account.is_frozen() {
+        return Err(TokenError::InvalidState.into());
     }
     
     token_account.state = AccountState::Initialized;
     
-    let serialized = token_account.try_to_vec()?;
-    token_account_info.data.borrow_mut()[..serialized.len()].copy_from_slice(&serialized);
+    TokenAccount::pack(token_account, &mut token_account_info.data.borrow_mut())?;
     
     msg!("Account thawed");
     Ok(())
 }
 
-use arch_program::account::next_account_info; 
\ No newline at end of file
+fn process_set_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    authority_type: AuthorityType,
+    new_authority: Option<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let account_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
+    
+    check_writable(account_info)?;
+    
+    // Mints and token accounts have different sizes, so the length says
+    // which one this is
+    if account_info.data_len() == TokenAccount::LEN {
+        let mut token_account = load_token_account(program_id, account_info)?;
+        
+        if token_account.is_frozen() {
+            return Err(TokenError::AccountFrozen.into());
+        }
+        
+        match authority_type {
+            AuthorityType::AccountOwner => {
+                validate_owner(program_id, &token_account.owner, authority_info, signers)?;
+                // Every account needs an owner
+                token_account.owner = new_authority.ok_or(TokenError::InvalidInstruction)?;
+                // The old owner's approval doesn't carry over
+                token_account.delegate = None;
+                token_account.delegated_amount = 0;
+            }
+            AuthorityType::CloseAccount => {
+                let close_authority = token_account.close_authority.unwrap_or(token_account.owner);
+                validate_owner(program_id, &close_authority, authority_info, signers)?;
+                token_account.close_authority = new_authority;
+            }
+            _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+        
+        TokenAccount::pack(token_account, &mut account_info.data.borrow_mut())?;
+    } else if account_info.data_len() == Mint::LEN {
+        let mut mint = load_mint(program_id, account_info)?;
+        
+        match authority_type {
+            AuthorityType::MintTokens => {
+                // Once removed, nobody can ever mint again
+                let mint_authority = mint.mint_authority.ok_or(TokenError::FixedSupply)?;
+                validate_owner(program_id, &mint_authority, authority_info, signers)?;
+                mint.mint_authority = new_authority;
+            }
+            AuthorityType::FreezeAccount => {
+                let freeze_authority = mint.freeze_authority.ok_or(TokenError::MintCannotFreeze)?;
+                validate_owner(program_id, &freeze_authority, authority_info, signers)?;
+                mint.freeze_authority = new_authority;
+            }
+            _ => return Err(TokenError::AuthorityTypeNotSupported.into()),
+        }
+        
+        Mint::pack(mint, &mut account_info.data.borrow_mut())?;
+    } else {
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    msg!("Set {:?} authority", authority_type);
+    Ok(())
+}
+
+fn process_close_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let token_account_info = next_account_info(account_info_iter)?;
+    let destination_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let signers = account_info_iter.as_slice();
+    
+    check_writable(token_account_info)?;
+    check_writable(destination_info)?;
+    let token_account = load_token_account(program_id, token_account_info)?;
+    
+    // The lamports would go nowhere
+    if token_account_info.key == destination_info.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    
+    if token_account.is_frozen() {
+        return Err(TokenError::AccountFrozen.into());
+    }
+    
+    // Closing would destroy the tokens without reducing the mint's supply;
+    // a native account's balance is its lamports, which aren't lost
+    if token_account.is_native.is_none() && token_account.amount != 0 {
+        return Err(TokenError::NonNativeHasBalance.into());
+    }
+    
+    let close_authority = token_account.close_authority.unwrap_or(token_account.owner);
+    validate_owner(program_id, &close_authority, authority_info, signers)?;
+    
+    let destination_lamports = destination_info
+        .lamports()
+        .checked_add(token_account_info.lamports())
+        .ok_or(TokenError::Overflow)?;
+    **destination_info.lamports.borrow_mut() = destination_lamports;
+    **token_account_info.lamports.borrow_mut() = 0;
+    
+    // Wipe the account and hand it back to the system program. Zeroed data
+    // alone would read as uninitialized, so a later instruction in the same
+    // transaction could send lamports back and initialize it again; once
+    // the account isn't ours every token instruction rejects it
+    token_account_info.data.borrow_mut().fill(0);
+    token_account_info.realloc(0, false)?;
+    token_account_info.assign(&Pubkey::system_program());
+    
+    msg!("Closed token account");
+    Ok(())
+}
+
+fn process_initialize_multisig(program_id: &Pubkey, accounts: &[AccountInfo], m: u8) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let multisig_info = next_account_info(account_info_iter)?;
+    let signer_infos = account_info_iter.as_slice();
+    
+    check_program_account(program_id, multisig_info)?;
+    check_writable(multisig_info)?;
+    
+    let mut multisig = Multisig::unpack_unchecked(&multisig_info.data.borrow())?;
+    if multisig.is_initialized() {
+        return Err(TokenError::AlreadyInitialized.into());
+    }
+    
+    if signer_infos.is_empty() || signer_infos.len() > MAX_SIGNERS {
+        return Err(TokenError::InvalidNumberOfProvidedSigners.into());
+    }
+    let n = u8::try_from(signer_infos.len()).map_err(|_| TokenError::InvalidNumberOfProvidedSigners)?;
+    if m == 0 || m > n {
+        return Err(TokenError::InvalidNumberOfRequiredSigners.into());
+    }
+    
+    for (signer, signer_info) in multisig.signers.iter_mut().zip(signer_infos) {
+        *signer = *signer_info.key;
+    }
+    multisig.m = m;
+    multisig.n = n;
+    multisig.is_initialized = true;
+    
+    Multisig::pack(multisig, &mut multisig_info.data.borrow_mut())?;
+    
+    msg!("Multisig initialized: {} of {} signers required", m, n);
+    Ok(())
+}
+
+/// Takes `amount` out of a delegate's allowance, removing the delegate once
+/// nothing is left so a spent approval can't linger on the account
+fn spend_delegation(token_account: &mut TokenAccount, amount: u64) -> ProgramResult {
+    token_account.delegated_amount = token_account
+        .delegated_amount
+        .checked_sub(amount)
+        .ok_or(TokenError::InsufficientDelegation)?;
+    if token_account.delegated_amount == 0 {
+        token_account.delegate = None;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// ACCOUNT CHECKS - Never Trust the Accounts You're Given
+// ============================================================================
+// Callers choose which accounts to pass, so every instruction checks that its
+// accounts are really this program's, writable where they change, and
+// initialized before use
+
+/// Only accounts owned by this program hold real mints and token accounts;
+/// anyone can create an account elsewhere with look-alike data
+fn check_program_account(program_id: &Pubkey, account_info: &AccountInfo) -> ProgramResult {
+    if account_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
+
+/// The account given as `owner_info` must be `expected_owner` and have
+/// signed. A multisig can't sign itself, so instead at least `m` of its
+/// signers must be among the signed `signers` passed after it
+fn validate_owner(
+    program_id: &Pubkey,
+    expected_owner: &Pubkey,
+    owner_info: &AccountInfo,
+    signers: &[AccountInfo],
+) -> ProgramResult {
+    if expected_owner != owner_info.key {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    
+    if owner_info.owner == program_id && owner_info.data_len() == Multisig::LEN {
+        let multisig = Multisig::unpack(&owner_info.data.borrow())?;
+        // Each multisig signer counts once, however often it's passed
+        let mut matched = [false; MAX_SIGNERS];
+        for signer in signers.iter().filter(|signer| signer.is_signer) {
+            for (position, key) in multisig.signers.iter().take(usize::from(multisig.n)).enumerate() {
+                if key == signer.key {
+                    matched[position] = true;
+                }
+            }
+        }
+        let num_signers = matched.iter().filter(|matched| **matched).count();
+        if num_signers < usize::from(multisig.m) {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        return Ok(());
+    }
+    
+    if !owner_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// The checked instructions make callers say how many decimals they think
+/// the mint has, catching amounts given in the wrong units
+fn check_decimals(mint: &Mint, expected_decimals: u8) -> ProgramResult {
+    if mint.decimals != expected_decimals {
+        return Err(TokenError::MintDecimalsMismatch.into());
+    }
+    Ok(())
+}
+
+fn check_writable(account_info: &AccountInfo) -> ProgramResult {
+    if !account_info.is_writable {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Loads a mint owned by this program that has been initialized
+fn load_mint(program_id: &Pubkey, mint_info: &AccountInfo) -> Result<Mint, ProgramError> {
+    check_program_account(program_id, mint_info)?;
+    let mint = Mint::unpack_unchecked(&mint_info.data.borrow())?;
+    if !mint.is_initialized() {
+        return Err(TokenError::UninitializedState.into());
+    }
+    Ok(mint)
+}
+
+/// Loads a token account owned by this program that has been initialized
+fn load_token_account(
+    program_id: &Pubkey,
+    account_info: &AccountInfo,
+) -> Result<TokenAccount, ProgramError> {
+    check_program_account(program_id, account_info)?;
+    let token_account = TokenAccount::unpack_unchecked(&account_info.data.borrow())?;
+    if !token_account.is_initialized() {
+        return Err(TokenError::UninitializedState.into());
+    }
+    Ok(token_account)
+}
@@ -4,17 +4,35 @@
 // These tests demonstrate real-world token operations on Arch Network
 // Students will complete TODOs to make tests pass and learn by doing!
 
+// The crate's lint policy is for on-chain code; tests unwrap and add freely
+#![allow(clippy::unwrap_used, clippy::arithmetic_side_effects)]
+
 #[cfg(test)]
 mod tests {
-    use super::*;
-    use arch_program::{program_pack::Pack, sanitized::ArchMessage};
-    use arch_sdk::{build_and_sign_transaction, generate_new_keypair, ArchRpcClient, Status};
-    use arch_test_sdk::{
-        constants::{BITCOIN_NETWORK, NODE1_ADDRESS},
+    use arch_program::{
+        account::MIN_ACCOUNT_LAMPORTS,
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sanitized::ArchMessage,
+        system_instruction,
+    };
+    use arch_sdk::{build_and_sign_transaction, generate_new_keypair, Status};
+    use associated_token_account::{
+        create_associated_token_account, create_associated_token_account_idempotent,
+        get_associated_token_address,
+    };
+    use arch_test_sdk::{constants::BITCOIN_NETWORK, logging::init_logging};
+    use bitcoin::key::Keypair;
+    // The local validator runs the program in-process, so these tests need
+    // no node, faucet or Bitcoin network
+    use local_validator::{
         helper::{
-            create_and_fund_account_with_faucet, read_account_info, send_transactions_and_wait,
+            create_and_fund_account_with_faucet, get_confirmations, mine_blocks,
+            pubkey_from_keypair, read_account_info, register_program, send_transactions_and_wait,
+            send_utxo,
         },
-        logging::init_logging,
+        LocalClient,
     };
     use serial_test::serial;
     use token_operations::*;
@@ -25,13 +43,12 @@ mod tests {
     // This test shows how to create a new token mint on Arch Network
     // A mint is like a "token factory" that can create new tokens
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_initialize_mint() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         // TODO: Generate keypairs for authority and mint
         // The authority controls who can mint tokens
@@ -57,12 +74,19 @@ mod tests {
             decimals: 9 // Most tokens use 9 decimals (like Bitcoin's satoshis)
         };
         
-        let instruction_data = initialize_mint_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&initialize_mint_instruction).unwrap();
         
         // TODO: Build and send the transaction
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction here */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(token_mint_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, false),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -75,7 +99,7 @@ mod tests {
         
         // TODO: Verify the mint was created correctly
         let token_mint_info = read_account_info(token_mint_pubkey);
-        let token_mint_data = Mint::try_from_slice(&token_mint_info.data).unwrap();
+        let token_mint_data = Mint::unpack(&token_mint_info.data).unwrap();
         
         assert_eq!(token_mint_data.decimals, 9);
         assert!(token_mint_data.is_initialized);
@@ -93,13 +117,12 @@ mod tests {
     // Token accounts are like wallets that hold specific tokens
     // Each token account is tied to one mint and one owner
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_initialize_account() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
         create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
@@ -122,13 +145,21 @@ mod tests {
         
         // TODO: Create the initialize account instruction
         let initialize_account_instruction = TokenInstruction::InitializeAccount;
-        let instruction_data = initialize_account_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&initialize_account_instruction).unwrap();
         
         // TODO: Build transaction with proper accounts
         // Account order matters! Check the instruction definition
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(token_account_pubkey, false),
+                        AccountMeta::new_readonly(token_mint_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, false),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -142,7 +173,7 @@ mod tests {
         // TODO: Verify the token account was created correctly
         let token_account_data = read_account_info(token_account_pubkey);
         let token_account: TokenAccount = 
-            TokenAccount::try_from_slice(&token_account_data.data).unwrap();
+            TokenAccount::unpack(&token_account_data.data).unwrap();
             
         assert_eq!(token_account.mint, token_mint_pubkey);
         assert_eq!(token_account.owner, authority_pubkey);
@@ -161,13 +192,12 @@ mod tests {
     // Minting creates new tokens and adds them to the total supply
     // Only the mint authority can mint new tokens
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_mint_to() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
         create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
@@ -181,12 +211,20 @@ mod tests {
         // TODO: Create mint instruction
         let mint_amount = 1000; // Mint 1000 tokens
         let mint_instruction = TokenInstruction::MintTo { amount: mint_amount };
-        let instruction_data = mint_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&mint_instruction).unwrap();
         
         // TODO: Build and send transaction
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(token_mint_pubkey, false),
+                        AccountMeta::new(authority_token_account_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, true),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -200,13 +238,13 @@ mod tests {
         // TODO: Verify tokens were minted
         let authority_token_account_info = read_account_info(authority_token_account_pubkey);
         let authority_token_account_data = 
-            TokenAccount::try_from_slice(&authority_token_account_info.data).unwrap();
+            TokenAccount::unpack(&authority_token_account_info.data).unwrap();
         
         assert_eq!(authority_token_account_data.amount, mint_amount);
         
         // TODO: Also check that mint supply increased
         let mint_info = read_account_info(token_mint_pubkey);
-        let mint_data = Mint::try_from_slice(&mint_info.data).unwrap();
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
         assert_eq!(mint_data.supply, mint_amount);
         
         println!("✅ Minted {} tokens successfully!", mint_amount);
@@ -220,13 +258,12 @@ mod tests {
     // Transfers move tokens from one account to another
     // The sender must have sufficient balance and sign the transaction
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_transfer() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         // TODO: Set up two users
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
@@ -257,11 +294,19 @@ mod tests {
         // TODO: Transfer tokens
         let transfer_amount = 250;
         let transfer_instruction = TokenInstruction::Transfer { amount: transfer_amount };
-        let instruction_data = transfer_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&transfer_instruction).unwrap();
         
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(authority_token_account_pubkey, false),
+                        AccountMeta::new(recipient_token_account_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, true),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -274,10 +319,10 @@ mod tests {
         
         // TODO: Verify the transfer worked
         let recipient_token_account_info = read_account_info(recipient_token_account_pubkey);
-        let recipient_data = TokenAccount::try_from_slice(&recipient_token_account_info.data).unwrap();
+        let recipient_data = TokenAccount::unpack(&recipient_token_account_info.data).unwrap();
         
         let sender_token_account_info = read_account_info(authority_token_account_pubkey);
-        let sender_data = TokenAccount::try_from_slice(&sender_token_account_info.data).unwrap();
+        let sender_data = TokenAccount::unpack(&sender_token_account_info.data).unwrap();
         
         assert_eq!(recipient_data.amount, transfer_amount);
         assert_eq!(sender_data.amount, initial_amount - transfer_amount);
@@ -293,13 +338,12 @@ mod tests {
     // Approval allows another account to spend tokens on your behalf
     // This is useful for smart contracts and automated trading
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_approve() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
         create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
@@ -328,11 +372,19 @@ mod tests {
         // TODO: Approve delegate to spend tokens
         let approve_amount = 500;
         let approve_instruction = TokenInstruction::Approve { amount: approve_amount };
-        let instruction_data = approve_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&approve_instruction).unwrap();
         
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(authority_token_account_pubkey, false),
+                        AccountMeta::new_readonly(delegate_token_account_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, true),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -346,7 +398,7 @@ mod tests {
         // TODO: Verify approval was set
         let authority_token_account_info = read_account_info(authority_token_account_pubkey);
         let authority_token_account_data = 
-            TokenAccount::try_from_slice(&authority_token_account_info.data).unwrap();
+            TokenAccount::unpack(&authority_token_account_info.data).unwrap();
         
         assert_eq!(authority_token_account_data.amount, 1000); // Original balance unchanged
         assert_eq!(authority_token_account_data.delegated_amount, approve_amount);
@@ -363,13 +415,12 @@ mod tests {
     // Burning permanently removes tokens from circulation
     // This reduces the total supply and can increase scarcity
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_burn() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
         create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
@@ -394,11 +445,19 @@ mod tests {
         // TODO: Burn some tokens
         let burn_amount = 300;
         let burn_instruction = TokenInstruction::Burn { amount: burn_amount };
-        let instruction_data = burn_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&burn_instruction).unwrap();
         
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(authority_token_account_pubkey, false),
+                        AccountMeta::new(token_mint_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, true),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -412,10 +471,10 @@ mod tests {
         // TODO: Verify tokens were burned
         let authority_token_account_info = read_account_info(authority_token_account_pubkey);
         let authority_token_account_data = 
-            TokenAccount::try_from_slice(&authority_token_account_info.data).unwrap();
+            TokenAccount::unpack(&authority_token_account_info.data).unwrap();
         
         let mint_info = read_account_info(token_mint_pubkey);
-        let mint_data = Mint::try_from_slice(&mint_info.data).unwrap();
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
         
         assert_eq!(authority_token_account_data.amount, initial_amount - burn_amount);
         assert_eq!(mint_data.supply, initial_amount - burn_amount);
@@ -431,13 +490,12 @@ mod tests {
     // Freezing prevents all token operations on an account
     // Useful for compliance and security purposes
     
-    #[ignore] // Remove this to run the test
     #[test]
     #[serial]
     fn test_freeze_account() {
         init_logging();
         
-        let client = ArchRpcClient::new(NODE1_ADDRESS);
+        let client = LocalClient::new();
         
         let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
         create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
@@ -464,11 +522,19 @@ mod tests {
         
         // TODO: Freeze the account
         let freeze_instruction = TokenInstruction::FreezeAccount;
-        let instruction_data = freeze_instruction.try_to_vec().unwrap();
+        let instruction_data = borsh::to_vec(&freeze_instruction).unwrap();
         
         let transaction = build_and_sign_transaction(
             ArchMessage::new(
-                &[/* TODO: Add your instruction */],
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(authority_token_account_pubkey, false),
+                        AccountMeta::new_readonly(token_mint_pubkey, false),
+                        AccountMeta::new_readonly(authority_pubkey, true),
+                    ],
+                    data: instruction_data,
+                }],
                 Some(authority_pubkey),
                 client.get_best_block_hash().unwrap(),
             ),
@@ -482,7 +548,7 @@ mod tests {
         // TODO: Verify account is frozen
         let token_account_info = read_account_info(authority_token_account_pubkey);
         let token_account_data = 
-            TokenAccount::try_from_slice(&token_account_info.data).unwrap();
+            TokenAccount::unpack(&token_account_info.data).unwrap();
         assert_eq!(token_account_data.state, AccountState::Frozen);
         
         println!("✅ Account frozen successfully!");
@@ -490,55 +556,1986 @@ mod tests {
     }
     
     // ========================================================================
-    // HELPER FUNCTIONS - Reusable Code for Tests
+    // TEST 8: Token Errors - Tell Failures Apart
     // ========================================================================
-    // These functions help reduce code duplication in tests
-    // Study them to understand common patterns
+    // Every token failure used to be a generic InvalidArgument
+    // TokenError codes say exactly which rule a transaction broke
     
-    fn create_account_helper(
-        client: &ArchRpcClient,
-        payer: &Pubkey,
-        new_account: &Pubkey,
-        payer_keypair: /* TODO: Add type */,
-        new_account_keypair: /* TODO: Add type */,
-        space: u64,
-    ) {
-        // TODO: Implement account creation helper
-        // This should create a new account with the specified space
-        // Use system_instruction::create_account
+    #[test]
+    #[serial]
+    fn test_token_errors() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (attacker_keypair, attacker_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&attacker_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) = 
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, other_mint_pubkey) = 
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, authority_token_account_pubkey) = 
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, other_token_account_pubkey) = 
+            initialize_token_account(&client, other_mint_pubkey, authority_keypair);
+        
+        mint_tokens(
+            &client,
+            &token_mint_pubkey,
+            &authority_token_account_pubkey,
+            &authority_pubkey,
+            authority_keypair,
+            100,
+        );
+        
+        // Only the mint authority can mint
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1 },
+            vec![
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new(authority_token_account_pubkey, false),
+                AccountMeta::new_readonly(attacker_pubkey, true),
+            ],
+            vec![attacker_keypair],
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        // Tokens can only move between accounts of the same mint
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 10 },
+            vec![
+                AccountMeta::new(authority_token_account_pubkey, false),
+                AccountMeta::new(other_token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::MintMismatch);
+        
+        // You can't send more than you have
+        let (_, recipient_token_account_pubkey) = 
+            initialize_token_account(&client, token_mint_pubkey, attacker_keypair);
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 101 },
+            vec![
+                AccountMeta::new(authority_token_account_pubkey, false),
+                AccountMeta::new(recipient_token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::InsufficientFunds);
+        
+        // Codes decode back to the error that produced them
+        assert_eq!(
+            TokenError::from_code(TokenError::AccountFrozen as u32),
+            Some(TokenError::AccountFrozen)
+        );
+        
+        println!("✅ Each failure reports its own TokenError!");
     }
     
-    fn initialize_mint_token(
-        client: &ArchRpcClient,
-        authority: Pubkey,
-        authority_keypair: /* TODO: Add type */,
-        freeze_authority: Option<&Pubkey>,
-    ) -> (/* TODO: Return type */, Pubkey) {
-        // TODO: Implement mint initialization helper
-        // This should create and initialize a new mint
-        // Return the mint keypair and pubkey
+    // ========================================================================
+    // TEST 9: Frozen Accounts - Freezing Actually Stops Tokens
+    // ========================================================================
+    // A frozen account can't send, receive, mint, burn or change its delegate
+    // until the freeze authority thaws it
+    
+    #[test]
+    #[serial]
+    fn test_frozen_account_rejects_operations() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (recipient_keypair, recipient_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&recipient_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) = initialize_mint_token(
+            &client,
+            authority_pubkey,
+            authority_keypair,
+            Some(&authority_pubkey),
+        );
+        let (_, frozen_account_pubkey) = 
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, recipient_token_account_pubkey) = 
+            initialize_token_account(&client, token_mint_pubkey, recipient_keypair);
+        
+        mint_tokens(&client, &token_mint_pubkey, &frozen_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        mint_tokens(&client, &token_mint_pubkey, &recipient_token_account_pubkey, &authority_pubkey, authority_keypair, 100);
+        
+        let freeze_accounts = vec![
+            AccountMeta::new(frozen_account_pubkey, false),
+            AccountMeta::new_readonly(token_mint_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ];
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::FreezeAccount,
+            freeze_accounts.clone(),
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        // Sending from the frozen account
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 10 },
+            vec![
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new(recipient_token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        // Sending to the frozen account
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 10 },
+            vec![
+                AccountMeta::new(recipient_token_account_pubkey, false),
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new_readonly(recipient_pubkey, true),
+            ],
+            vec![recipient_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        // Minting into it
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 10 },
+            vec![
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        // Burning from it
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 10 },
+            vec![
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        // Approving or revoking a delegate
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Approve { amount: 10 },
+            vec![
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new_readonly(recipient_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Revoke,
+            vec![
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::AccountFrozen);
+        
+        // Freezing twice is a mistake too
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::FreezeAccount,
+            freeze_accounts.clone(),
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::InvalidState);
+        
+        // Nothing moved while the account was frozen
+        let frozen_info = read_account_info(frozen_account_pubkey);
+        let frozen_data = TokenAccount::unpack(&frozen_info.data).unwrap();
+        assert_eq!(frozen_data.amount, 1000);
+        
+        // Once thawed, transfers work again
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::ThawAccount,
+            freeze_accounts,
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 10 },
+            vec![
+                AccountMeta::new(frozen_account_pubkey, false),
+                AccountMeta::new(recipient_token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        println!("✅ Frozen accounts reject every operation until thawed!");
     }
     
-    fn initialize_token_account(
-        client: &ArchRpcClient,
-        mint: Pubkey,
-        owner_keypair: /* TODO: Add type */,
-    ) -> (/* TODO: Return type */, Pubkey) {
-        // TODO: Implement token account initialization helper
-        // This should create and initialize a new token account
-        // Return the account keypair and pubkey
+    // ========================================================================
+    // TEST 10: Account Guards - Attacks That Must Fail
+    // ========================================================================
+    // Anyone can send any accounts to the program, so each of these tests
+    // replays an attack the program has to reject
+    
+    /// Reinitializing an existing mint would let anyone take over minting
+    #[test]
+    #[serial]
+    fn test_reinitialize_mint_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (attacker_keypair, attacker_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&attacker_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeMint { decimals: 9 },
+            vec![
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new_readonly(attacker_pubkey, false),
+            ],
+            vec![attacker_keypair],
+        );
+        assert_token_error(&status, TokenError::AlreadyInitialized);
+        
+        let mint_info = read_account_info(token_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.mint_authority, Some(authority_pubkey));
     }
     
-    fn mint_tokens(
-        client: &ArchRpcClient,
-        mint: &Pubkey,
-        destination: &Pubkey,
-        authority: &Pubkey,
-        authority_keypair: /* TODO: Add type */,
-        amount: u64,
-    ) {
-        // TODO: Implement token minting helper
-        // This should mint tokens to the specified account
+    /// Reinitializing a token account would hand its balance to a new owner
+    #[test]
+    #[serial]
+    fn test_reinitialize_account_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (attacker_keypair, attacker_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&attacker_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeAccount,
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(token_mint_pubkey, false),
+                AccountMeta::new_readonly(attacker_pubkey, false),
+            ],
+            vec![attacker_keypair],
+        );
+        assert_token_error(&status, TokenError::AlreadyInitialized);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.owner, authority_pubkey);
+        assert_eq!(account_data.amount, 1000);
+    }
+    
+    /// A "mint" owned by another program can hold whatever data its creator
+    /// likes, so it must never be accepted as a mint
+    #[test]
+    #[serial]
+    fn test_mint_not_owned_by_program_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (attacker_keypair, attacker_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&attacker_keypair, BITCOIN_NETWORK);
+        
+        let (fake_mint_keypair, fake_mint_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        let fake_program_id = Pubkey::from_slice(b"attacker_controlled_fake_program");
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[system_instruction::create_account(
+                    &attacker_pubkey,
+                    &fake_mint_pubkey,
+                    MIN_ACCOUNT_LAMPORTS,
+                    Mint::LEN as u64,
+                    &fake_program_id,
+                )],
+                Some(attacker_pubkey),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![attacker_keypair, fake_mint_keypair],
+            BITCOIN_NETWORK,
+        );
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_eq!(processed_transactions[0].status, Status::Processed);
+        
+        let (token_account_keypair, token_account_pubkey, _) =
+            generate_new_keypair(BITCOIN_NETWORK);
+        create_account_helper(
+            &client,
+            &attacker_pubkey,
+            &token_account_pubkey,
+            attacker_keypair,
+            token_account_keypair,
+            TokenAccount::LEN as u64,
+        );
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeAccount,
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(fake_mint_pubkey, false),
+                AccountMeta::new_readonly(attacker_pubkey, false),
+            ],
+            vec![attacker_keypair],
+        );
+        assert_failed_with(&status, "IncorrectProgramId");
+    }
+    
+    /// Accounts the program changes have to be passed as writable
+    #[test]
+    #[serial]
+    fn test_read_only_mint_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            vec![
+                AccountMeta::new_readonly(token_mint_pubkey, false),
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_failed_with(&status, "InvalidArgument");
+    }
+    
+    /// The authority of a worthless mint must not be able to mint into
+    /// accounts of another mint
+    #[test]
+    #[serial]
+    fn test_mint_to_with_wrong_mint_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (attacker_keypair, attacker_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&attacker_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, attacker_mint_pubkey) =
+            initialize_mint_token(&client, attacker_pubkey, attacker_keypair, None);
+        let (_, victim_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, attacker_keypair);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            vec![
+                AccountMeta::new(attacker_mint_pubkey, false),
+                AccountMeta::new(victim_account_pubkey, false),
+                AccountMeta::new_readonly(attacker_pubkey, true),
+            ],
+            vec![attacker_keypair],
+        );
+        assert_token_error(&status, TokenError::MintMismatch);
+        
+        let account_info = read_account_info(victim_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 0);
+    }
+    
+    /// Burning has to reduce the supply of the account's own mint
+    #[test]
+    #[serial]
+    fn test_burn_with_wrong_mint_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, other_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, other_account_pubkey) =
+            initialize_token_account(&client, other_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        mint_tokens(&client, &other_mint_pubkey, &other_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 500 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(other_mint_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::MintMismatch);
+        
+        let mint_info = read_account_info(other_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.supply, 1000);
+    }
+    
+    /// Accounts that were created but never initialized hold no tokens
+    #[test]
+    #[serial]
+    fn test_uninitialized_account_fails() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let (uninitialized_keypair, uninitialized_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_account_helper(
+            &client,
+            &authority_pubkey,
+            &uninitialized_pubkey,
+            authority_keypair,
+            uninitialized_keypair,
+            TokenAccount::LEN as u64,
+        );
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 100 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(uninitialized_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::UninitializedState);
+    }
+    
+    /// Sending tokens to the same account must not create any
+    #[test]
+    #[serial]
+    fn test_self_transfer_keeps_balance() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 400 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 1000);
+        
+        // A delegate's self-transfer must fit its allowance but, as in SPL,
+        // doesn't use any of it up
+        let (delegate_keypair, delegate_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&delegate_keypair, BITCOIN_NETWORK);
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Approve { amount: 300 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(delegate_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let delegate_self_transfer = |amount: u64| {
+            send_token_instruction(
+                &client,
+                TokenInstruction::Transfer { amount },
+                vec![
+                    AccountMeta::new(token_account_pubkey, false),
+                    AccountMeta::new(token_account_pubkey, false),
+                    AccountMeta::new_readonly(delegate_pubkey, true),
+                ],
+                vec![delegate_keypair],
+            )
+        };
+        assert_eq!(delegate_self_transfer(300), Status::Processed);
+        assert_token_error(&delegate_self_transfer(301), TokenError::InsufficientDelegation);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 1000);
+        assert_eq!(account_data.delegate, Some(delegate_pubkey));
+        assert_eq!(account_data.delegated_amount, 300);
+    }
+    
+    // ========================================================================
+    // TEST 11: Account Layouts - Fixed-Size Packing
+    // ========================================================================
+    // Mints and token accounts use the same byte layout as SPL and APL
+    // tokens, so every field sits at a known offset whatever its value
+    
+    #[test]
+    #[serial]
+    fn test_account_layouts() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        // No freeze authority, so that COption is None
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let mint_data = read_account_info(token_mint_pubkey).data;
+        assert_eq!(mint_data.len(), Mint::LEN);
+        assert_eq!(mint_data[0..4], [1, 0, 0, 0]); // mint_authority: Some
+        assert_eq!(mint_data[4..36], authority_pubkey.serialize());
+        assert_eq!(mint_data[36..44], 1000u64.to_le_bytes()); // supply
+        assert_eq!(mint_data[44], 9); // decimals
+        assert_eq!(mint_data[45], 1); // is_initialized
+        assert_eq!(mint_data[46..82], [0; 36]); // freeze_authority: None
+        
+        let account_data = read_account_info(token_account_pubkey).data;
+        assert_eq!(account_data.len(), TokenAccount::LEN);
+        assert_eq!(account_data[0..32], token_mint_pubkey.serialize());
+        assert_eq!(account_data[32..64], authority_pubkey.serialize());
+        assert_eq!(account_data[64..72], 1000u64.to_le_bytes()); // amount
+        assert_eq!(account_data[108], 1); // state: Initialized
+        
+        // Packing and unpacking round-trips every field
+        let token_account = TokenAccount::unpack(&account_data).unwrap();
+        let mut repacked = vec![0; TokenAccount::LEN];
+        TokenAccount::pack(token_account.clone(), &mut repacked).unwrap();
+        assert_eq!(repacked, account_data);
+        assert_eq!(TokenAccount::unpack(&repacked).unwrap(), token_account);
+        
+        println!("✅ Accounts use the fixed token layout!");
+    }
+    
+    // ========================================================================
+    // TEST 12: Set Authority - Hand Over or Give Up Control
+    // ========================================================================
+    // Authorities can be changed by whoever holds them, and removing the mint
+    // authority fixes the token's supply forever
+    
+    #[test]
+    #[serial]
+    fn test_set_mint_authority() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (new_authority_keypair, new_authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&new_authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        
+        // Only the current authority can hand minting over
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::MintTokens,
+            Some(new_authority_pubkey),
+            new_authority_keypair,
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::MintTokens,
+            Some(new_authority_pubkey),
+            authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        // The old authority can't mint any more, the new one can
+        let mint_to_accounts = |authority: Pubkey| {
+            vec![
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(authority, true),
+            ]
+        };
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            mint_to_accounts(authority_pubkey),
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &new_authority_pubkey, new_authority_keypair, 1000);
+        
+        // Removing the authority fixes the supply for good
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::MintTokens,
+            None,
+            new_authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            mint_to_accounts(new_authority_pubkey),
+            vec![new_authority_keypair],
+        );
+        assert_token_error(&status, TokenError::FixedSupply);
+        
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::MintTokens,
+            Some(new_authority_pubkey),
+            new_authority_keypair,
+        );
+        assert_token_error(&status, TokenError::FixedSupply);
+        
+        let mint_info = read_account_info(token_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.mint_authority, None);
+        assert_eq!(mint_data.supply, 1000);
+        
+        // Token accounts have no mint authority to change
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::MintTokens,
+            Some(authority_pubkey),
+            authority_keypair,
+        );
+        assert_token_error(&status, TokenError::AuthorityTypeNotSupported);
+        
+        println!("✅ Mint authority handed over, then removed for a fixed supply!");
+    }
+    
+    #[test]
+    #[serial]
+    fn test_set_account_owner() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (new_owner_keypair, new_owner_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&new_owner_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, new_owner_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, new_owner_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        // A pending approval shouldn't survive the change of owner
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Approve { amount: 500 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(new_owner_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::AccountOwner,
+            Some(new_owner_pubkey),
+            authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.owner, new_owner_pubkey);
+        assert_eq!(account_data.delegate, None);
+        assert_eq!(account_data.delegated_amount, 0);
+        
+        // The new owner can spend the tokens
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 1000 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(new_owner_account_pubkey, false),
+                AccountMeta::new_readonly(new_owner_pubkey, true),
+            ],
+            vec![new_owner_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        // Accounts always need an owner
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::AccountOwner,
+            None,
+            new_owner_keypair,
+        );
+        assert_token_error(&status, TokenError::InvalidInstruction);
+        
+        println!("✅ Token account handed to a new owner!");
+    }
+    
+    #[test]
+    #[serial]
+    fn test_set_freeze_authority() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (new_authority_keypair, new_authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&new_authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) = initialize_mint_token(
+            &client,
+            authority_pubkey,
+            authority_keypair,
+            Some(&authority_pubkey),
+        );
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        
+        // Only the current freeze authority can hand it over
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::FreezeAccount,
+            Some(new_authority_pubkey),
+            new_authority_keypair,
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::FreezeAccount,
+            Some(new_authority_pubkey),
+            authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let mint_info = read_account_info(token_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.freeze_authority, Some(new_authority_pubkey));
+        // Minting is a separate authority and stays put
+        assert_eq!(mint_data.mint_authority, Some(authority_pubkey));
+        
+        // The old authority can't freeze any more, the new one can
+        let freeze = |instruction: TokenInstruction, authority_keypair: Keypair| {
+            send_token_instruction(
+                &client,
+                instruction,
+                vec![
+                    AccountMeta::new(token_account_pubkey, false),
+                    AccountMeta::new_readonly(token_mint_pubkey, false),
+                    AccountMeta::new_readonly(pubkey_from_keypair(&authority_keypair), true),
+                ],
+                vec![authority_keypair],
+            )
+        };
+        assert_token_error(
+            &freeze(TokenInstruction::FreezeAccount, authority_keypair),
+            TokenError::OwnerMismatch,
+        );
+        assert_eq!(freeze(TokenInstruction::FreezeAccount, new_authority_keypair), Status::Processed);
+        assert_eq!(freeze(TokenInstruction::ThawAccount, new_authority_keypair), Status::Processed);
+        
+        // Removing the authority means no account of this mint can ever be
+        // frozen again
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::FreezeAccount,
+            None,
+            new_authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let mint_info = read_account_info(token_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.freeze_authority, None);
+        
+        assert_token_error(
+            &freeze(TokenInstruction::FreezeAccount, new_authority_keypair),
+            TokenError::MintCannotFreeze,
+        );
+        
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::FreezeAccount,
+            Some(new_authority_pubkey),
+            new_authority_keypair,
+        );
+        assert_token_error(&status, TokenError::MintCannotFreeze);
+        
+        // Token accounts have no freeze authority to change
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::FreezeAccount,
+            Some(authority_pubkey),
+            authority_keypair,
+        );
+        assert_token_error(&status, TokenError::AuthorityTypeNotSupported);
+        
+        println!("✅ Freeze authority handed over, then removed for good!");
+    }
+    
+    #[test]
+    #[serial]
+    fn test_set_close_authority() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (closer_keypair, closer_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&closer_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        
+        // Without a close authority the owner decides, so only the owner can
+        // appoint one
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::CloseAccount,
+            Some(closer_pubkey),
+            closer_keypair,
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::CloseAccount,
+            Some(closer_pubkey),
+            authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.close_authority, Some(closer_pubkey));
+        assert_eq!(account_data.owner, authority_pubkey);
+        
+        // The owner no longer gets a say in closing the account
+        let close = |authority_keypair: Keypair| {
+            let authority = pubkey_from_keypair(&authority_keypair);
+            send_token_instruction(
+                &client,
+                TokenInstruction::CloseAccount,
+                vec![
+                    AccountMeta::new(token_account_pubkey, false),
+                    AccountMeta::new(authority, false),
+                    AccountMeta::new_readonly(authority, true),
+                ],
+                vec![authority_keypair],
+            )
+        };
+        assert_token_error(&close(authority_keypair), TokenError::OwnerMismatch);
+        
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::CloseAccount,
+            None,
+            authority_keypair,
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        // Clearing it hands closing back to the owner
+        let status = set_authority(
+            &client,
+            &token_account_pubkey,
+            AuthorityType::CloseAccount,
+            None,
+            closer_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.close_authority, None);
+        
+        assert_token_error(&close(closer_keypair), TokenError::OwnerMismatch);
+        assert_eq!(close(authority_keypair), Status::Processed);
+        
+        // Mints have no close authority to change
+        let status = set_authority(
+            &client,
+            &token_mint_pubkey,
+            AuthorityType::CloseAccount,
+            Some(closer_pubkey),
+            authority_keypair,
+        );
+        assert_token_error(&status, TokenError::AuthorityTypeNotSupported);
+        
+        println!("✅ Close authority appointed, then cleared back to the owner!");
+    }
+    
+    // ========================================================================
+    // TEST 13: Close Account - Reclaim Lamports From Empty Accounts
+    // ========================================================================
+    // Closing an empty token account returns its lamports and wipes it
+    
+    #[test]
+    #[serial]
+    fn test_close_account() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let close_accounts = vec![
+            AccountMeta::new(token_account_pubkey, false),
+            AccountMeta::new(authority_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ];
+        
+        // Tokens would disappear without leaving the supply
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::CloseAccount,
+            close_accounts.clone(),
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::NonNativeHasBalance);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 1000 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let authority_lamports = read_account_info(authority_pubkey).lamports;
+        let account_lamports = read_account_info(token_account_pubkey).lamports;
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::CloseAccount,
+            close_accounts,
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        // The lamports moved over and the account no longer belongs to the
+        // token program
+        assert_eq!(
+            read_account_info(authority_pubkey).lamports,
+            authority_lamports + account_lamports
+        );
+        let closed_info = read_account_info(token_account_pubkey);
+        assert_eq!(closed_info.lamports, 0);
+        assert!(closed_info.data.is_empty());
+        assert_eq!(closed_info.owner, Pubkey::system_program());
+        
+        println!("✅ Closed the empty account and reclaimed its lamports!");
+    }
+    
+    /// Sending lamports back to a just-closed account mustn't make it usable
+    /// again within the same transaction
+    #[test]
+    #[serial]
+    fn test_closed_account_cannot_be_revived() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[
+                    token_instruction(
+                        TokenInstruction::CloseAccount,
+                        vec![
+                            AccountMeta::new(token_account_pubkey, false),
+                            AccountMeta::new(authority_pubkey, false),
+                            AccountMeta::new_readonly(authority_pubkey, true),
+                        ],
+                    ),
+                    system_instruction::transfer(
+                        &authority_pubkey,
+                        &token_account_pubkey,
+                        MIN_ACCOUNT_LAMPORTS,
+                    ),
+                    token_instruction(
+                        TokenInstruction::InitializeAccount,
+                        vec![
+                            AccountMeta::new(token_account_pubkey, false),
+                            AccountMeta::new_readonly(token_mint_pubkey, false),
+                            AccountMeta::new_readonly(authority_pubkey, false),
+                        ],
+                    ),
+                ],
+                Some(authority_pubkey),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![authority_keypair],
+            BITCOIN_NETWORK,
+        );
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_failed_with(&processed_transactions[0].status, "IncorrectProgramId");
+        
+        // The failed transaction changed nothing, so the account is still open
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.owner, authority_pubkey);
+    }
+    
+    // ========================================================================
+    // TEST 14: Checked Instructions - Catch Unit Mistakes
+    // ========================================================================
+    // The checked variants take the decimals the caller expects and fail if
+    // the mint disagrees, so "1 token" is never sent as 1 base unit
+    
+    #[test]
+    #[serial]
+    fn test_checked_instructions() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (recipient_keypair, recipient_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&recipient_keypair, BITCOIN_NETWORK);
+        
+        // The helper creates mints with 9 decimals
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, other_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, recipient_token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, recipient_keypair);
+        
+        let mint_to_accounts = vec![
+            AccountMeta::new(token_mint_pubkey, false),
+            AccountMeta::new(token_account_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ];
+        let transfer_accounts = |mint: Pubkey| {
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(mint, false),
+                AccountMeta::new(recipient_token_account_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ]
+        };
+        let burn_accounts = vec![
+            AccountMeta::new(token_account_pubkey, false),
+            AccountMeta::new(token_mint_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ];
+        let approve_accounts = vec![
+            AccountMeta::new(token_account_pubkey, false),
+            AccountMeta::new_readonly(token_mint_pubkey, false),
+            AccountMeta::new_readonly(recipient_pubkey, false),
+            AccountMeta::new_readonly(authority_pubkey, true),
+        ];
+        
+        // Each instruction fails with the wrong decimals...
+        let wrong_decimals = [
+            (TokenInstruction::MintToChecked { amount: 1000, decimals: 6 }, mint_to_accounts.clone()),
+            (TokenInstruction::TransferChecked { amount: 100, decimals: 6 }, transfer_accounts(token_mint_pubkey)),
+            (TokenInstruction::BurnChecked { amount: 100, decimals: 6 }, burn_accounts.clone()),
+            (TokenInstruction::ApproveChecked { amount: 100, decimals: 6 }, approve_accounts.clone()),
+        ];
+        for (instruction, accounts) in wrong_decimals {
+            let status = send_token_instruction(&client, instruction, accounts, vec![authority_keypair]);
+            assert_token_error(&status, TokenError::MintDecimalsMismatch);
+        }
+        
+        // ...and succeeds with the right ones
+        let right_decimals = [
+            (TokenInstruction::MintToChecked { amount: 1000, decimals: 9 }, mint_to_accounts),
+            (TokenInstruction::TransferChecked { amount: 100, decimals: 9 }, transfer_accounts(token_mint_pubkey)),
+            (TokenInstruction::BurnChecked { amount: 100, decimals: 9 }, burn_accounts),
+            (TokenInstruction::ApproveChecked { amount: 100, decimals: 9 }, approve_accounts),
+        ];
+        for (instruction, accounts) in right_decimals {
+            let status = send_token_instruction(&client, instruction, accounts, vec![authority_keypair]);
+            assert_eq!(status, Status::Processed);
+        }
+        
+        // The mint passed in has to be the accounts' mint, even if its
+        // decimals match
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::TransferChecked { amount: 100, decimals: 9 },
+            transfer_accounts(other_mint_pubkey),
+            vec![authority_keypair],
+        );
+        assert_token_error(&status, TokenError::MintMismatch);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 800);
+        assert_eq!(account_data.delegate, Some(recipient_pubkey));
+        
+        let recipient_info = read_account_info(recipient_token_account_pubkey);
+        let recipient_data = TokenAccount::unpack(&recipient_info.data).unwrap();
+        assert_eq!(recipient_data.amount, 100);
+        
+        println!("✅ Checked instructions verified the mint's decimals!");
+    }
+    
+    // ========================================================================
+    // TEST 15: Delegate Burning - Spend an Allowance Down to Zero
+    // ========================================================================
+    // A delegate can burn as well as transfer within its allowance, and the
+    // approval disappears once the allowance is used up
+    
+    #[test]
+    #[serial]
+    fn test_delegate_burn() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (delegate_keypair, delegate_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&delegate_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, authority_keypair);
+        let (_, delegate_token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, delegate_keypair);
+        mint_tokens(&client, &token_mint_pubkey, &token_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Approve { amount: 300 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new_readonly(delegate_pubkey, false),
+                AccountMeta::new_readonly(authority_pubkey, true),
+            ],
+            vec![authority_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let burn_accounts = |authority: Pubkey, is_signer: bool| {
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(token_mint_pubkey, false),
+                AccountMeta::new_readonly(authority, is_signer),
+            ]
+        };
+        
+        // Naming the owner isn't enough, they have to sign
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 100 },
+            burn_accounts(authority_pubkey, false),
+            vec![delegate_keypair],
+        );
+        assert_failed_with(&status, "MissingRequiredSignature");
+        
+        // The delegate can't burn more than it was approved for
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 400 },
+            burn_accounts(delegate_pubkey, true),
+            vec![delegate_keypair],
+        );
+        assert_token_error(&status, TokenError::InsufficientDelegation);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 200 },
+            burn_accounts(delegate_pubkey, true),
+            vec![delegate_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 800);
+        assert_eq!(account_data.delegate, Some(delegate_pubkey));
+        assert_eq!(account_data.delegated_amount, 100);
+        
+        // Transferring the rest of the allowance clears the delegate
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 100 },
+            vec![
+                AccountMeta::new(token_account_pubkey, false),
+                AccountMeta::new(delegate_token_account_pubkey, false),
+                AccountMeta::new_readonly(delegate_pubkey, true),
+            ],
+            vec![delegate_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 700);
+        assert_eq!(account_data.delegate, None);
+        assert_eq!(account_data.delegated_amount, 0);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Burn { amount: 1 },
+            burn_accounts(delegate_pubkey, true),
+            vec![delegate_keypair],
+        );
+        assert_token_error(&status, TokenError::OwnerMismatch);
+        
+        let mint_info = read_account_info(token_mint_pubkey);
+        let mint_data = Mint::unpack(&mint_info.data).unwrap();
+        assert_eq!(mint_data.supply, 800);
+        
+        println!("✅ Delegate burned within its allowance until it ran out!");
+    }
+    
+    // ========================================================================
+    // TEST 16: Multisig - Require Several Signatures
+    // ========================================================================
+    // A 2-of-3 multisig stands in for a single authority: instructions pass
+    // the multisig's address, followed by the signers who approve
+    
+    #[test]
+    #[serial]
+    fn test_multisig_authority() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (signer1_keypair, signer1_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&signer1_keypair, BITCOIN_NETWORK);
+        let (signer2_keypair, signer2_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        let (_, signer3_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        let (outsider_keypair, outsider_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        
+        let (multisig_keypair, multisig_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_account_helper(
+            &client,
+            &signer1_pubkey,
+            &multisig_pubkey,
+            signer1_keypair,
+            multisig_keypair,
+            Multisig::LEN as u64,
+        );
+        
+        let multisig_accounts = vec![
+            AccountMeta::new(multisig_pubkey, false),
+            AccountMeta::new_readonly(signer1_pubkey, false),
+            AccountMeta::new_readonly(signer2_pubkey, false),
+            AccountMeta::new_readonly(signer3_pubkey, false),
+        ];
+        
+        // More required signatures than signers can never be satisfied
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeMultisig { m: 4 },
+            multisig_accounts.clone(),
+            vec![signer1_keypair],
+        );
+        assert_token_error(&status, TokenError::InvalidNumberOfRequiredSigners);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeMultisig { m: 2 },
+            multisig_accounts.clone(),
+            vec![signer1_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::InitializeMultisig { m: 1 },
+            multisig_accounts,
+            vec![signer1_keypair],
+        );
+        assert_token_error(&status, TokenError::AlreadyInitialized);
+        
+        let multisig_info = read_account_info(multisig_pubkey);
+        let multisig_data = Multisig::unpack(&multisig_info.data).unwrap();
+        assert_eq!((multisig_data.m, multisig_data.n), (2, 3));
+        assert_eq!(multisig_data.signers[..3], [signer1_pubkey, signer2_pubkey, signer3_pubkey]);
+        
+        // Hand the mint authority and a token account to the multisig
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, signer1_pubkey, signer1_keypair, None);
+        let (_, token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, signer1_keypair);
+        let (_, recipient_token_account_pubkey) =
+            initialize_token_account(&client, token_mint_pubkey, signer1_keypair);
+        for (account, authority_type) in [
+            (token_mint_pubkey, AuthorityType::MintTokens),
+            (token_account_pubkey, AuthorityType::AccountOwner),
+        ] {
+            let status = set_authority(&client, &account, authority_type, Some(multisig_pubkey), signer1_keypair);
+            assert_eq!(status, Status::Processed);
+        }
+        
+        let with_signers = |mut accounts: Vec<AccountMeta>, signers: &[Pubkey]| {
+            accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, true)));
+            accounts
+        };
+        let mint_to_accounts = vec![
+            AccountMeta::new(token_mint_pubkey, false),
+            AccountMeta::new(token_account_pubkey, false),
+            AccountMeta::new_readonly(multisig_pubkey, false),
+        ];
+        
+        // One signature isn't enough, even if it's passed twice
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            with_signers(mint_to_accounts.clone(), &[signer1_pubkey, signer1_pubkey]),
+            vec![signer1_keypair],
+        );
+        assert_failed_with(&status, "MissingRequiredSignature");
+        
+        // Signatures from outside the multisig don't count
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            with_signers(mint_to_accounts.clone(), &[signer1_pubkey, outsider_pubkey]),
+            vec![signer1_keypair, outsider_keypair],
+        );
+        assert_failed_with(&status, "MissingRequiredSignature");
+        
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::MintTo { amount: 1000 },
+            with_signers(mint_to_accounts, &[signer1_pubkey, signer2_pubkey]),
+            vec![signer1_keypair, signer2_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        // The multisig-owned account spends with two signatures too
+        let status = send_token_instruction(
+            &client,
+            TokenInstruction::Transfer { amount: 400 },
+            with_signers(
+                vec![
+                    AccountMeta::new(token_account_pubkey, false),
+                    AccountMeta::new(recipient_token_account_pubkey, false),
+                    AccountMeta::new_readonly(multisig_pubkey, false),
+                ],
+                &[signer1_pubkey, signer2_pubkey],
+            ),
+            vec![signer1_keypair, signer2_keypair],
+        );
+        assert_eq!(status, Status::Processed);
+        
+        let account_info = read_account_info(token_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 600);
+        
+        println!("✅ 2-of-3 multisig minted and transferred tokens!");
+    }
+    
+    // ========================================================================
+    // TEST 17: Associated Token Accounts - One Address per Wallet and Mint
+    // ========================================================================
+    // The companion program creates a wallet's token account at an address
+    // derived from the wallet and mint, so nobody has to pass addresses around
+    
+    #[test]
+    #[serial]
+    fn test_associated_token_account() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        // The wallet doesn't need to sign or pay for its own account
+        let (_, wallet_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        
+        let token_program = token_program_id();
+        let associated_program = associated_token_program_id();
+        let associated_account_pubkey = get_associated_token_address(
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        
+        let create = create_associated_token_account(
+            &authority_pubkey,
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        assert_eq!(send_instruction(&client, create.clone(), authority_keypair), Status::Processed);
+        
+        // It's an ordinary token account, owned by the wallet
+        let account_info = read_account_info(associated_account_pubkey);
+        assert_eq!(account_info.owner, token_program);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.mint, token_mint_pubkey);
+        assert_eq!(account_data.owner, wallet_pubkey);
+        
+        mint_tokens(&client, &token_mint_pubkey, &associated_account_pubkey, &authority_pubkey, authority_keypair, 1000);
+        
+        // Creating it again fails, but the idempotent form succeeds and
+        // leaves the account alone
+        assert_failed_with(
+            &send_instruction(&client, create, authority_keypair),
+            "AccountAlreadyInitialized",
+        );
+        
+        let create_idempotent = create_associated_token_account_idempotent(
+            &authority_pubkey,
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        assert_eq!(send_instruction(&client, create_idempotent, authority_keypair), Status::Processed);
+        
+        let account_info = read_account_info(associated_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.amount, 1000);
+        
+        // Only the derived address is accepted
+        let mut wrong_address = create_associated_token_account(
+            &authority_pubkey,
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        let (_, other_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        wrong_address.accounts[1] = AccountMeta::new(other_pubkey, false);
+        assert_failed_with(
+            &send_instruction(&client, wrong_address, authority_keypair),
+            "InvalidSeeds",
+        );
+        
+        // Its signature for the address only goes to the real system program
+        let mut wrong_system_program = create_associated_token_account(
+            &authority_pubkey,
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        wrong_system_program.accounts[4] = AccountMeta::new_readonly(token_program, false);
+        assert_failed_with(
+            &send_instruction(&client, wrong_system_program, authority_keypair),
+            "IncorrectProgramId",
+        );
+        
+        // The idempotent form creates the account if it's missing, too
+        let (_, other_wallet_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        let create_idempotent = create_associated_token_account_idempotent(
+            &authority_pubkey,
+            &other_wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        assert_eq!(send_instruction(&client, create_idempotent, authority_keypair), Status::Processed);
+        
+        let other_account_pubkey = get_associated_token_address(
+            &other_wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        let account_info = read_account_info(other_account_pubkey);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.owner, other_wallet_pubkey);
+        
+        println!("✅ Created associated token accounts at derived addresses!");
+    }
+    
+    /// Sending lamports to a wallet's associated address first mustn't stop
+    /// its account from ever being created
+    #[test]
+    #[serial]
+    fn test_associated_token_account_at_funded_address() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, wallet_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        
+        let token_program = token_program_id();
+        let associated_program = associated_token_program_id();
+        let associated_account_pubkey = get_associated_token_address(
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        
+        let status = send_instruction(
+            &client,
+            system_instruction::transfer(&authority_pubkey, &associated_account_pubkey, 1),
+            authority_keypair,
+        );
+        assert_eq!(status, Status::Processed);
+        assert_eq!(read_account_info(associated_account_pubkey).lamports, 1);
+        
+        let create = create_associated_token_account(
+            &authority_pubkey,
+            &wallet_pubkey,
+            &token_mint_pubkey,
+            &token_program,
+            &associated_program,
+        );
+        assert_eq!(send_instruction(&client, create, authority_keypair), Status::Processed);
+        
+        // The funder only paid what was missing
+        let account_info = read_account_info(associated_account_pubkey);
+        assert_eq!(account_info.owner, token_program);
+        assert_eq!(account_info.lamports, MIN_ACCOUNT_LAMPORTS);
+        let account_data = TokenAccount::unpack(&account_info.data).unwrap();
+        assert_eq!(account_data.mint, token_mint_pubkey);
+        assert_eq!(account_data.owner, wallet_pubkey);
+        
+        println!("✅ Created an associated token account at a pre-funded address!");
+    }
+    
+    // ========================================================================
+    // TEST 18: Bitcoin UTXOs - Anchor a Token Account to Bitcoin
+    // ========================================================================
+    // Arch accounts can be anchored to a Bitcoin output; the local regtest
+    // chain sends it and confirms it as blocks are mined
+    
+    #[test]
+    #[serial]
+    fn test_account_utxo_confirmations() {
+        init_logging();
+        
+        let client = LocalClient::new();
+        
+        let (authority_keypair, authority_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_and_fund_account_with_faucet(&authority_keypair, BITCOIN_NETWORK);
+        
+        let (_, token_mint_pubkey) =
+            initialize_mint_token(&client, authority_pubkey, authority_keypair, None);
+        
+        // send_utxo pays the mint's address and mines the payment into a block
+        let (txid, vout) = send_utxo(token_mint_pubkey);
+        assert_eq!(read_account_info(token_mint_pubkey).utxo, format!("{}:{}", txid, vout));
+        assert_eq!(get_confirmations(&txid), Some(1));
+        
+        // Every block mined on top adds a confirmation
+        mine_blocks(5);
+        assert_eq!(get_confirmations(&txid), Some(6));
+        
+        // The mint itself is untouched by being anchored
+        let token_mint_info = read_account_info(token_mint_pubkey);
+        assert!(Mint::unpack(&token_mint_info.data).unwrap().is_initialized);
+        
+        println!("✅ Mint anchored to {}:{} with 6 confirmations!", txid, vout);
+    }
+    
+    // ========================================================================
+    // HELPER FUNCTIONS - Reusable Code for Tests
+    // ========================================================================
+    // These functions help reduce code duplication in tests
+    // Study them to understand common patterns
+    
+    /// Registers the lesson program with the local validator and returns its
+    /// id, standing in for deploying it.
+    fn token_program_id() -> Pubkey {
+        let program_id = Pubkey::from_slice(b"bitcoin_zombies_token_operations");
+        register_program(&program_id, process_instruction);
+        program_id
+    }
+    
+    /// Registers the associated token account program, as `token_program_id`
+    /// does for the token program
+    fn associated_token_program_id() -> Pubkey {
+        let program_id = Pubkey::from_slice(b"bitcoin_zombies_associated_token");
+        register_program(&program_id, associated_token_account::process_instruction);
+        program_id
+    }
+    
+    fn create_account_helper(
+        client: &LocalClient,
+        payer: &Pubkey,
+        new_account: &Pubkey,
+        payer_keypair: Keypair,
+        new_account_keypair: Keypair,
+        space: u64,
+    ) {
+        // The new account is owned by the token program so it can write the
+        // account's data
+        let instruction = system_instruction::create_account(
+            payer,
+            new_account,
+            MIN_ACCOUNT_LAMPORTS,
+            space,
+            &token_program_id(),
+        );
+        
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[instruction],
+                Some(*payer),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![payer_keypair, new_account_keypair],
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_eq!(processed_transactions[0].status, Status::Processed);
+    }
+    
+    fn initialize_mint_token(
+        client: &LocalClient,
+        authority: Pubkey,
+        authority_keypair: Keypair,
+        freeze_authority: Option<&Pubkey>,
+    ) -> (Keypair, Pubkey) {
+        let (token_mint_keypair, token_mint_pubkey, _) = generate_new_keypair(BITCOIN_NETWORK);
+        create_account_helper(
+            client,
+            &authority,
+            &token_mint_pubkey,
+            authority_keypair,
+            token_mint_keypair,
+            Mint::LEN as u64,
+        );
+        
+        let mut accounts = vec![
+            AccountMeta::new(token_mint_pubkey, false),
+            AccountMeta::new_readonly(authority, false),
+        ];
+        if let Some(freeze_authority) = freeze_authority {
+            accounts.push(AccountMeta::new_readonly(*freeze_authority, false));
+        }
+        
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts,
+                    data: borsh::to_vec(&TokenInstruction::InitializeMint { decimals: 9 }).unwrap(),
+                }],
+                Some(authority),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![authority_keypair],
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_eq!(processed_transactions[0].status, Status::Processed);
+        
+        (token_mint_keypair, token_mint_pubkey)
+    }
+    
+    fn initialize_token_account(
+        client: &LocalClient,
+        mint: Pubkey,
+        owner_keypair: Keypair,
+    ) -> (Keypair, Pubkey) {
+        let owner = pubkey_from_keypair(&owner_keypair);
+        let (token_account_keypair, token_account_pubkey, _) =
+            generate_new_keypair(BITCOIN_NETWORK);
+        create_account_helper(
+            client,
+            &owner,
+            &token_account_pubkey,
+            owner_keypair,
+            token_account_keypair,
+            TokenAccount::LEN as u64,
+        );
+        
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(token_account_pubkey, false),
+                        AccountMeta::new_readonly(mint, false),
+                        AccountMeta::new_readonly(owner, false),
+                    ],
+                    data: borsh::to_vec(&TokenInstruction::InitializeAccount).unwrap(),
+                }],
+                Some(owner),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![owner_keypair],
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_eq!(processed_transactions[0].status, Status::Processed);
+        
+        (token_account_keypair, token_account_pubkey)
+    }
+    
+    fn mint_tokens(
+        client: &LocalClient,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        authority: &Pubkey,
+        authority_keypair: Keypair,
+        amount: u64,
+    ) {
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[Instruction {
+                    program_id: token_program_id(),
+                    accounts: vec![
+                        AccountMeta::new(*mint, false),
+                        AccountMeta::new(*destination, false),
+                        AccountMeta::new_readonly(*authority, true),
+                    ],
+                    data: borsh::to_vec(&TokenInstruction::MintTo { amount }).unwrap(),
+                }],
+                Some(*authority),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![authority_keypair],
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        assert_eq!(processed_transactions[0].status, Status::Processed);
+    }
+    
+    fn token_instruction(instruction: TokenInstruction, accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction {
+            program_id: token_program_id(),
+            accounts,
+            data: borsh::to_vec(&instruction).unwrap(),
+        }
+    }
+    
+    /// Sends any single instruction paid for by `payer` and returns its status
+    fn send_instruction(client: &LocalClient, instruction: Instruction, payer: Keypair) -> Status {
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[instruction],
+                Some(pubkey_from_keypair(&payer)),
+                client.get_best_block_hash().unwrap(),
+            ),
+            vec![payer],
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        processed_transactions[0].status.clone()
+    }
+    
+    /// Sends a single token instruction paid for by the first signer and
+    /// returns its status, for checking instructions that should fail
+    fn send_token_instruction(
+        client: &LocalClient,
+        instruction: TokenInstruction,
+        accounts: Vec<AccountMeta>,
+        signers: Vec<Keypair>,
+    ) -> Status {
+        let payer = pubkey_from_keypair(&signers[0]);
+        let transaction = build_and_sign_transaction(
+            ArchMessage::new(
+                &[token_instruction(instruction, accounts)],
+                Some(payer),
+                client.get_best_block_hash().unwrap(),
+            ),
+            signers,
+            BITCOIN_NETWORK,
+        );
+        
+        let processed_transactions = send_transactions_and_wait(vec![transaction]);
+        processed_transactions[0].status.clone()
+    }
+    
+    fn set_authority(
+        client: &LocalClient,
+        account: &Pubkey,
+        authority_type: AuthorityType,
+        new_authority: Option<Pubkey>,
+        authority_keypair: Keypair,
+    ) -> Status {
+        let authority = pubkey_from_keypair(&authority_keypair);
+        send_token_instruction(
+            client,
+            TokenInstruction::SetAuthority { authority_type, new_authority },
+            vec![
+                AccountMeta::new(*account, false),
+                AccountMeta::new_readonly(authority, true),
+            ],
+            vec![authority_keypair],
+        )
+    }
+    
+    /// Failed transactions report `custom program error: 0x..` with the
+    /// TokenError's code
+    fn assert_token_error(status: &Status, error: TokenError) {
+        assert_failed_with(status, &format!("custom program error: {:#x}", error as u32));
+    }
+    
+    /// Checks the transaction failed with `expected` in its error, e.g. the
+    /// name of a built-in `ProgramError`
+    fn assert_failed_with(status: &Status, expected: &str) {
+        match status {
+            Status::Failed(message) => assert!(
+                message.contains(expected),
+                "expected {}, got: {}",
+                expected,
+                message
+            ),
+            status => panic!("expected {}, got {:?}", expected, status),
+        }
     }
     
     // ========================================================================
@@ -546,8 +2543,6 @@ mod tests {
     // ========================================================================
     // Try implementing these additional features:
     
-    // TODO: Implement multisig token operations
-    // TODO: Add associated token account creation
     // TODO: Create a token swap function
     // TODO: Implement token metadata
     // TODO: Add governance token features
@@ -1,18 +1,18 @@
+use std::env;
 use std::fs;
 use std::path::Path;
-use std::process::Command;
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
 use tempfile::TempDir;
-use tracing::{info, error};
+use tracing::info;
 
-pub async fn compile_and_run(code: &str) -> Result<String, String> {
-    info!("Creating temporary directory for Arch program");
-    
-    // Create a temporary directory
-    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
-    let temp_path = temp_dir.path();
-    
-    // Create Cargo.toml for the Arch program
-    let cargo_toml = r#"[package]
+use quote::quote;
+
+use crate::lessons::{self, Lesson};
+use crate::metering;
+use crate::requirements::check_requirements;
+
+const DEFAULT_CARGO_TOML: &str = r#"[package]
 name = "temp_arch_program"
 version = "0.1.0"
 edition = "2021"
@@ -23,7 +23,17 @@ arch-program = "0.1.0"
 [lib]
 crate-type = ["cdylib"]
 "#;
+
+/// Lays out a cargo project for a submission: the lesson's Cargo.toml and
+/// tests when a lesson is given, otherwise a bare Arch program crate.
+pub fn prepare_workspace(code: &str, lesson: Option<&Lesson>) -> Result<TempDir, String> {
+    info!("Creating temporary directory for Arch program");
     
+    // Create a temporary directory
+    let temp_dir = TempDir::new().map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let temp_path = temp_dir.path();
+    
+    let cargo_toml = lesson.map_or(DEFAULT_CARGO_TOML, |lesson| lesson.cargo_toml.as_str());
     let cargo_toml_path = temp_path.join("Cargo.toml");
     fs::write(&cargo_toml_path, cargo_toml)
         .map_err(|e| format!("Failed to write Cargo.toml: {}", e))?;
@@ -37,51 +47,348 @@ crate-type = ["cdylib"]
     fs::write(&lib_path, code)
         .map_err(|e| format!("Failed to write lib.rs: {}", e))?;
     
+    if let Some(lesson) = lesson {
+        if !lesson.tests.is_empty() {
+            let tests_dir = temp_path.join("tests");
+            fs::create_dir(&tests_dir)
+                .map_err(|e| format!("Failed to create tests directory: {}", e))?;
+            for (file_name, contents) in &lesson.tests {
+                fs::write(tests_dir.join(file_name), contents)
+                    .map_err(|e| format!("Failed to write test {}: {}", file_name, e))?;
+            }
+        }
+    }
+    
+    Ok(temp_dir)
+}
+
+/// Resolves an optional lesson name from a request into a loaded lesson.
+pub fn resolve_lesson(lesson: Option<&str>) -> Result<Option<Lesson>, String> {
+    lesson.map(lessons::load_lesson).transpose()
+}
+
+/// Builds a command for a Rust tool running inside a workspace, pinned to the
+/// runner toolchain when `ARCH_RUNNER_TOOLCHAIN` is set.
+pub fn toolchain_command(program: &str, workspace: &Path) -> Command {
+    let mut command = Command::new(program);
+    command.current_dir(workspace);
+    if let Ok(toolchain) = env::var("ARCH_RUNNER_TOOLCHAIN") {
+        command.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+    command
+}
+
+pub fn cargo_command(workspace: &Path) -> Command {
+    toolchain_command("cargo", workspace)
+}
+
+/// Limit on building a submission and the harness around it.
+pub const COMPILE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Limit on running a compiled submission. Metering can't see into const
+/// fns or std, so this is what stops a submission that never finishes.
+pub const EXECUTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Runs a command to completion off the async runtime's worker threads.
+/// Returns `None` if it outlives `limit`, after killing it along with
+/// anything it started, such as the test binary `cargo test` runs.
+pub async fn run_command(command: Command, limit: Duration) -> Result<Option<Output>, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut command = tokio::process::Command::from(command);
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true);
+    
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", program, e))?;
+    let process_group = child.id();
+    
+    match tokio::time::timeout(limit, child.wait_with_output()).await {
+        Ok(output) => output
+            .map(Some)
+            .map_err(|e| format!("Failed to execute {}: {}", program, e)),
+        Err(_) => {
+            if let Some(process_group) = process_group {
+                // SAFETY: killpg only sends a signal; the group is the one
+                // the child was started in above
+                unsafe {
+                    libc::killpg(process_group as libc::pid_t, libc::SIGKILL);
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+pub async fn compile_and_run(
+    code: &str,
+    lesson: Option<&str>,
+    options: &RunOptions,
+) -> Result<String, String> {
+    let lesson = resolve_lesson(lesson)?;
+    
+    // Structural checks are instant, so report them before spending time on cargo
+    if let Some(lesson) = &lesson {
+        check_lesson_requirements(code, lesson)?;
+    }
+    
+    let temp_dir = prepare_workspace(code, lesson.as_ref())?;
+    let temp_path = temp_dir.path();
+    
     info!("Compiling Arch program");
     
     // Try to compile the program
-    let compile_output = Command::new("cargo")
-        .args(&["check", "--lib"])
-        .current_dir(temp_path)
-        .output()
-        .map_err(|e| format!("Failed to execute cargo: {}", e))?;
+    let mut check = cargo_command(temp_path);
+    check.args(["check", "--lib"]);
+    let Some(compile_output) = run_command(check, COMPILE_TIMEOUT).await? else {
+        return Err(compile_timeout());
+    };
     
     if !compile_output.status.success() {
         let stderr = String::from_utf8_lossy(&compile_output.stderr);
         return Err(format!("Compilation failed:\n{}", stderr));
     }
     
-    // For now, we'll simulate running the program by analyzing the code
-    // In a real implementation, you'd want to use a proper Arch runtime
-    let output = simulate_arch_execution(code)?;
+    info!("Executing Arch program");
+    
+    let entrypoint = entrypoint_name(lesson.as_ref());
+    let instrumented = format!(
+        "{}\n{}",
+        metering::instrument(code)?,
+        execution_harness(entrypoint, options)?
+    );
+    fs::write(temp_path.join("src").join("lib.rs"), instrumented)
+        .map_err(|e| format!("Failed to write lib.rs: {}", e))?;
+    
+    let run_output = run_harness(temp_path, "__zombies_harness").await?;
+    
+    let stdout = String::from_utf8_lossy(&run_output.stdout);
+    let Some(execution) = parse_execution(&stdout) else {
+        let stderr = String::from_utf8_lossy(&run_output.stderr);
+        return Err(format!("Execution failed:\n{}", stderr));
+    };
+    
+    let mut output = String::new();
+    for line in &execution.logs {
+        output.push_str(&format!("Program log: {}\n", line));
+    }
+    if output.is_empty() {
+        output.push_str("Program executed successfully (no output)\n");
+    }
+    output.push_str(&format!(
+        "Program consumed {} of {} compute units\n",
+        execution.compute_units, options.compute_budget
+    ));
+    
+    match execution.status {
+        ExecutionStatus::Success => {
+            output.push_str("Program completed successfully\n");
+            info!("Arch program executed successfully");
+            Ok(output)
+        }
+        ExecutionStatus::Failed(err) => Err(format!("{}Program failed: {}", output, err)),
+        ExecutionStatus::Panicked(message) if message == metering::BUDGET_EXCEEDED => Err(format!(
+            "{}Program failed: compute budget exceeded ({} compute units)",
+            output, options.compute_budget
+        )),
+        ExecutionStatus::Panicked(message) => {
+            Err(format!("{}Program panicked: {}", output, message))
+        }
+    }
+}
+
+/// Builds a workspace's test harness, then runs the tests matching `filter`
+/// with their output shown. Building first keeps compile time out of the
+/// execution timeout. A failed build comes back as its output, so callers
+/// report it the same way as a run that printed nothing.
+pub async fn run_harness(workspace: &Path, filter: &str) -> Result<Output, String> {
+    let mut build = cargo_command(workspace);
+    build.args(["test", "--lib", "--no-run", "--quiet"]);
+    let Some(build_output) = run_command(build, COMPILE_TIMEOUT).await? else {
+        return Err(compile_timeout());
+    };
+    if !build_output.status.success() {
+        return Ok(build_output);
+    }
     
-    info!("Arch program executed successfully");
-    Ok(output)
+    let mut run = cargo_command(workspace);
+    run.args(["test", "--lib", "--quiet", filter, "--", "--nocapture"]);
+    run_command(run, EXECUTION_TIMEOUT).await?.ok_or_else(|| {
+        format!(
+            "Program failed: execution timed out after {} seconds",
+            EXECUTION_TIMEOUT.as_secs()
+        )
+    })
 }
 
-fn simulate_arch_execution(code: &str) -> Result<String, String> {
-    let mut output = String::new();
+fn compile_timeout() -> String {
+    format!("Compilation timed out after {} seconds", COMPILE_TIMEOUT.as_secs())
+}
+
+const DEFAULT_ENTRYPOINT: &str = "process_instruction";
+
+/// Reports unmet lesson requirements as a single error listing each one.
+pub fn check_lesson_requirements(code: &str, lesson: &Lesson) -> Result<(), String> {
+    let violations = check_requirements(code, &lesson.requirements);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = violations
+        .iter()
+        .map(|violation| match violation.line {
+            Some(line) => format!("- line {}: {}", line, violation.message),
+            None => format!("- {}", violation.message),
+        })
+        .collect();
+    Err(format!(
+        "Lesson requirements not met:\n{}",
+        details.join("\n")
+    ))
+}
+
+/// The function a lesson registers with `entrypoint!`.
+pub fn entrypoint_name(lesson: Option<&Lesson>) -> &str {
+    lesson
+        .and_then(|lesson| lesson.requirements.entrypoint.as_deref())
+        .unwrap_or(DEFAULT_ENTRYPOINT)
+}
+
+const BEGIN_MARKER: &str = "__ZOMBIES_BEGIN__";
+const RESULT_MARKER: &str = "__ZOMBIES_RESULT__";
+
+/// Inputs for executing a submission once it compiles.
+pub struct RunOptions {
+    pub instruction_data: Vec<u8>,
+    pub compute_budget: u64,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            instruction_data: Vec::new(),
+            compute_budget: metering::DEFAULT_COMPUTE_BUDGET,
+        }
+    }
+}
+
+enum ExecutionStatus {
+    Success,
+    Failed(String),
+    Panicked(String),
+}
+
+struct Execution {
+    logs: Vec<String>,
+    compute_units: u64,
+    status: ExecutionStatus,
+}
+
+/// Test module appended to the instrumented submission. Running it through
+/// `cargo test` lets it call the entrypoint even when it isn't `pub`.
+fn execution_harness(entrypoint: &str, options: &RunOptions) -> Result<String, String> {
+    let entrypoint: syn::Ident = syn::parse_str(entrypoint)
+        .map_err(|e| format!("Invalid entrypoint name {}: {}", entrypoint, e))?;
+    let instruction_data = &options.instruction_data;
+    let compute_budget = options.compute_budget;
     
-    // Look for msg! macro calls and extract the messages
-    for line in code.lines() {
-        let trimmed = line.trim();
-        if trimmed.starts_with("msg!(") {
-            // Extract the message from msg!("Hello, Arch World!");
-            if let Some(start) = trimmed.find('"') {
-                if let Some(end) = trimmed.rfind('"') {
-                    if start < end {
-                        let message = &trimmed[start + 1..end];
-                        output.push_str(&format!("Program log: {}\n", message));
+    Ok(quote! {
+        #[cfg(test)]
+        mod __zombies_harness {
+            #[test]
+            fn run() {
+                let program_id = arch_program::pubkey::Pubkey::default();
+                let instruction_data: &[u8] = &[#(#instruction_data),*];
+                
+                super::__zombies_meter::reset(#compute_budget);
+                println!(#BEGIN_MARKER);
+                let result = std::panic::catch_unwind(|| {
+                    super::#entrypoint(&program_id, &[], instruction_data)
+                });
+                let units = super::__zombies_meter::consumed();
+                
+                match result {
+                    Ok(Ok(())) => println!("{} {} ok", #RESULT_MARKER, units),
+                    Ok(Err(err)) => println!("{} {} error {:?}", #RESULT_MARKER, units, err),
+                    Err(panic) => {
+                        let message = panic
+                            .downcast_ref::<&str>()
+                            .map(|message| message.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        println!("{} {} panic {}", #RESULT_MARKER, units, message);
                     }
                 }
             }
         }
     }
+    .to_string())
+}
+
+/// Reads program output between the harness markers, e.g.
+/// `__ZOMBIES_RESULT__ 42 error InvalidArgument`.
+fn parse_execution(stdout: &str) -> Option<Execution> {
+    let mut lines = stdout.lines().skip_while(|line| *line != BEGIN_MARKER).skip(1);
+    let mut logs = Vec::new();
     
-    if output.is_empty() {
-        output.push_str("Program executed successfully (no output)\n");
+    for line in lines.by_ref() {
+        let Some(result) = line.strip_prefix(RESULT_MARKER) else {
+            logs.push(line.to_string());
+            continue;
+        };
+        
+        let mut parts = result.trim_start().splitn(3, ' ');
+        let compute_units = parts.next()?.parse().ok()?;
+        let kind = parts.next()?;
+        let detail = parts.next().unwrap_or_default().to_string();
+        let status = match kind {
+            "ok" => ExecutionStatus::Success,
+            "error" => ExecutionStatus::Failed(detail),
+            _ => ExecutionStatus::Panicked(detail),
+        };
+        
+        return Some(Execution {
+            logs,
+            compute_units,
+            status,
+        });
     }
     
-    output.push_str("Program completed successfully\n");
-    Ok(output)
-} 
\ No newline at end of file
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn run_command_returns_output() {
+        let mut command = Command::new("echo");
+        command.arg("hello");
+        let output = run_command(command, Duration::from_secs(5)).await.unwrap().unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "hello\n");
+    }
+
+    #[tokio::test]
+    async fn run_command_kills_on_timeout() {
+        // The shell waits on a child, like cargo waiting on a test binary
+        let mut command = Command::new("sh");
+        command.args(["-c", "sleep 30 & wait"]);
+        let started = Instant::now();
+        let output = run_command(command, Duration::from_millis(200)).await.unwrap();
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn run_command_reports_missing_programs() {
+        let command = Command::new("definitely-not-a-real-program");
+        let err = run_command(command, Duration::from_secs(5)).await.unwrap_err();
+        assert!(err.starts_with("Failed to execute definitely-not-a-real-program"));
+    }
+}
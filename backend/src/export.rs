@@ -0,0 +1,371 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use toml_edit::DocumentMut;
+use tracing::{info, warn};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::arch_runner::{cargo_command, prepare_workspace, resolve_lesson, run_command, COMPILE_TIMEOUT};
+
+type Archive = ZipWriter<std::io::Cursor<Vec<u8>>>;
+
+const DEPENDENCY_SECTIONS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Where path dependencies from outside the lesson go in the archive.
+const BUNDLE_DIR: &str = "deps";
+
+/// Packages a submission as a zipped cargo project, adding the compiled
+/// library under `artifacts/` when the build succeeds. A lesson's path
+/// dependencies are bundled with it, so the project builds on its own.
+pub async fn export_project(code: &str, lesson: Option<&str>) -> Result<Vec<u8>, String> {
+    let lesson = resolve_lesson(lesson)?;
+    let temp_dir = prepare_workspace(code, lesson.as_ref())?;
+    let temp_path = temp_dir.path();
+    let project_name = lesson.as_ref().map_or("arch_program", |lesson| lesson.name.as_str());
+
+    let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    // Add the sources before building so target/ never ends up in the archive
+    match &lesson {
+        Some(lesson) => {
+            // The workspace's manifest has absolute paths that only resolve
+            // on this server, so the archive gets the lesson's own instead
+            let (manifest, bundled) = bundle_path_dependencies(&lesson.dir)?;
+            let workspace_files = |path: &Path| path != temp_path.join("Cargo.toml");
+            add_dir(&mut zip, temp_path, project_name, options, &workspace_files)?;
+            add_file(&mut zip, &format!("{}/Cargo.toml", project_name), manifest.as_bytes(), options)?;
+
+            for krate in &bundled {
+                let prefix = format!("{}/{}", project_name, archive_path(&krate.location));
+                let crate_files = |path: &Path| {
+                    let nested = bundled.iter().any(|other| other.source_dir == path);
+                    !nested && path != krate.source_dir.join("Cargo.toml") && !is_build_output(path)
+                };
+                add_dir(&mut zip, &krate.source_dir, &prefix, options, &crate_files)?;
+                add_file(&mut zip, &format!("{}/Cargo.toml", prefix), krate.manifest.as_bytes(), options)?;
+            }
+        }
+        None => add_dir(&mut zip, temp_path, project_name, options, &|_| true)?,
+    }
+
+    info!("Building export artifact");
+    let mut build = cargo_command(temp_path);
+    build.args(["build", "--lib", "--release"]);
+    match run_command(build, COMPILE_TIMEOUT).await? {
+        Some(build_output) if build_output.status.success() => {
+            let release_dir = temp_path.join("target").join("release");
+            for entry in fs::read_dir(&release_dir)
+                .map_err(|e| format!("Failed to read build output: {}", e))?
+                .flatten()
+            {
+                let path = entry.path();
+                if !is_library_artifact(&path) {
+                    continue;
+                }
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                let contents = fs::read(&path)
+                    .map_err(|e| format!("Failed to read artifact {}: {}", file_name, e))?;
+                add_file(&mut zip, &format!("{}/artifacts/{}", project_name, file_name), &contents, options)?;
+            }
+        }
+        Some(_) => warn!("Export build failed, packaging sources only"),
+        None => warn!("Export build timed out, packaging sources only"),
+    }
+
+    let archive = zip
+        .finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+    Ok(archive.into_inner())
+}
+
+/// A path dependency packaged alongside the project.
+#[derive(Debug)]
+struct BundledCrate {
+    source_dir: PathBuf,
+    /// Where the crate goes, relative to the project root.
+    location: PathBuf,
+    /// Its Cargo.toml with paths rewritten to point within the archive.
+    manifest: String,
+}
+
+/// Finds the lesson's path dependencies, and theirs, and lays them out for
+/// the archive: crates inside the lesson keep their place and the rest go
+/// under `deps/`. Returns the lesson's own manifest and the bundled crates,
+/// each manifest keeping its formatting with only moved paths rewritten.
+fn bundle_path_dependencies(lesson_dir: &Path) -> Result<(String, Vec<BundledCrate>), String> {
+    let root = fs::canonicalize(lesson_dir)
+        .map_err(|e| format!("Failed to resolve lesson directory: {}", e))?;
+
+    // Crate directories and where they go, the lesson itself first
+    let mut locations = vec![(root.clone(), PathBuf::new())];
+    let mut manifests = Vec::new();
+    let mut next = 0;
+    while let Some((dir, _)) = locations.get(next).cloned() {
+        next += 1;
+        let manifest_path = dir.join("Cargo.toml");
+        let manifest: DocumentMut = fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?
+            .parse()
+            .map_err(|e| format!("Failed to parse {}: {}", manifest_path.display(), e))?;
+
+        for path in path_dependencies(&manifest) {
+            let target = fs::canonicalize(dir.join(&path))
+                .map_err(|e| format!("Failed to resolve path dependency {}: {}", path, e))?;
+            if locations.iter().any(|(known, _)| *known == target) {
+                continue;
+            }
+            let location = match target.strip_prefix(&root) {
+                Ok(inside) => inside.to_path_buf(),
+                Err(_) => bundle_location(&target, &locations),
+            };
+            locations.push((target, location));
+        }
+        manifests.push((dir, manifest));
+    }
+
+    let mut root_manifest = String::new();
+    let mut bundled = Vec::new();
+    for (dir, mut manifest) in manifests {
+        let location = location_of(&dir, &locations);
+        rewrite_path_dependencies(&mut manifest, |path| {
+            let target = fs::canonicalize(dir.join(path)).ok()?;
+            Some(archive_path(&relative_path(&location, &location_of(&target, &locations))))
+        });
+        if dir == root {
+            root_manifest = manifest.to_string();
+        } else {
+            bundled.push(BundledCrate {
+                source_dir: dir,
+                location,
+                manifest: manifest.to_string(),
+            });
+        }
+    }
+    Ok((root_manifest, bundled))
+}
+
+fn path_dependencies(manifest: &DocumentMut) -> Vec<String> {
+    DEPENDENCY_SECTIONS
+        .iter()
+        .filter_map(|section| manifest.get(section)?.as_table_like())
+        .flat_map(|dependencies| dependencies.iter())
+        .filter_map(|(_, dependency)| Some(dependency.get("path")?.as_str()?.to_string()))
+        .collect()
+}
+
+/// Replaces each dependency path that `rewrite` maps to a new value,
+/// keeping the surrounding formatting.
+fn rewrite_path_dependencies(manifest: &mut DocumentMut, rewrite: impl Fn(&str) -> Option<String>) {
+    for section in DEPENDENCY_SECTIONS {
+        let Some(dependencies) = manifest.get_mut(section).and_then(|item| item.as_table_like_mut())
+        else {
+            continue;
+        };
+        for (_, dependency) in dependencies.iter_mut() {
+            let Some(path) = dependency.get_mut("path").and_then(|path| path.as_value_mut()) else {
+                continue;
+            };
+            let Some(rewritten) = path.as_str().and_then(&rewrite) else {
+                continue;
+            };
+            if path.as_str() != Some(rewritten.as_str()) {
+                let decor = path.decor().clone();
+                *path = rewritten.into();
+                *path.decor_mut() = decor;
+            }
+        }
+    }
+}
+
+/// A free spot under `deps/` named after the crate's directory.
+fn bundle_location(dir: &Path, locations: &[(PathBuf, PathBuf)]) -> PathBuf {
+    let name = dir
+        .file_name()
+        .map_or_else(|| "crate".to_string(), |name| name.to_string_lossy().into_owned());
+    let taken = |location: &PathBuf| locations.iter().any(|(_, known)| known == location);
+    let mut location = Path::new(BUNDLE_DIR).join(&name);
+    let mut suffix = 2;
+    while taken(&location) {
+        location = Path::new(BUNDLE_DIR).join(format!("{}-{}", name, suffix));
+        suffix += 1;
+    }
+    location
+}
+
+fn location_of(dir: &Path, locations: &[(PathBuf, PathBuf)]) -> PathBuf {
+    locations
+        .iter()
+        .find(|(known, _)| known == dir)
+        .map(|(_, location)| location.clone())
+        .unwrap_or_default()
+}
+
+/// The path from one project-relative directory to another.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..from.len() {
+        path.push("..");
+    }
+    for component in &to[common..] {
+        path.push(component);
+    }
+    if path.as_os_str().is_empty() {
+        path.push(".");
+    }
+    path
+}
+
+/// A relative path with `/` separators, as cargo and zip both accept.
+fn archive_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn is_build_output(path: &Path) -> bool {
+    matches!(
+        path.file_name().and_then(|name| name.to_str()),
+        Some("target" | "Cargo.lock" | ".git")
+    )
+}
+
+fn add_file(zip: &mut Archive, archive_path: &str, contents: &[u8], options: SimpleFileOptions) -> Result<(), String> {
+    zip.start_file(archive_path, options)
+        .map_err(|e| format!("Failed to add {} to archive: {}", archive_path, e))?;
+    zip.write_all(contents)
+        .map_err(|e| format!("Failed to add {} to archive: {}", archive_path, e))
+}
+
+/// Adds the files under `root` that `include` accepts, beneath `prefix`.
+fn add_dir(
+    zip: &mut Archive,
+    root: &Path,
+    prefix: &str,
+    options: SimpleFileOptions,
+    include: &dyn Fn(&Path) -> bool,
+) -> Result<(), String> {
+    add_entries(zip, root, root, prefix, options, include)
+}
+
+fn add_entries(
+    zip: &mut Archive,
+    root: &Path,
+    dir: &Path,
+    prefix: &str,
+    options: SimpleFileOptions,
+    include: &dyn Fn(&Path) -> bool,
+) -> Result<(), String> {
+    let mut entries: Vec<_> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read workspace: {}", e))?
+        .flatten()
+        .collect();
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        if !include(&path) {
+            continue;
+        }
+        let relative = path
+            .strip_prefix(root)
+            .map_err(|e| format!("Failed to resolve workspace path: {}", e))?;
+        let archive_path = format!("{}/{}", prefix, archive_path(relative));
+
+        if path.is_dir() {
+            zip.add_directory(archive_path, options)
+                .map_err(|e| format!("Failed to add directory to archive: {}", e))?;
+            add_entries(zip, root, &path, prefix, options, include)?;
+        } else {
+            let contents = fs::read(&path)
+                .map_err(|e| format!("Failed to read {}: {}", relative.display(), e))?;
+            add_file(zip, &archive_path, &contents, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_library_artifact(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("so" | "dylib" | "dll" | "rlib")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    #[test]
+    fn relative_paths_between_locations() {
+        assert_eq!(relative_path(Path::new(""), Path::new("deps/shared")), Path::new("deps/shared"));
+        assert_eq!(relative_path(Path::new("deps/shared"), Path::new("")), Path::new("../.."));
+        assert_eq!(relative_path(Path::new("deps/shared"), Path::new("inner")), Path::new("../../inner"));
+        assert_eq!(relative_path(Path::new("inner"), Path::new("inner")), Path::new("."));
+    }
+
+    #[test]
+    fn path_dependencies_are_bundled_with_relative_manifests() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let lesson = temp.path().join("lessons").join("my_lesson");
+        write(
+            &lesson.join("Cargo.toml"),
+            r#"[package]
+name = "my_lesson"
+
+[dependencies]
+inner = { path = "inner" }
+
+[dev-dependencies]
+# Tooling that lives outside the lesson
+shared = { path = "../../shared", features = ["extra"] }
+"#,
+        );
+        write(&lesson.join("inner").join("Cargo.toml"), "[package]\nname = \"inner\"\n");
+        write(
+            &temp.path().join("shared").join("Cargo.toml"),
+            r#"[package]
+name = "shared"
+
+[dependencies.my_lesson]
+path = "../lessons/my_lesson"
+optional = true
+
+[dependencies]
+inner = { path = "../lessons/my_lesson/inner" }
+"#,
+        );
+
+        let (manifest, bundled) = bundle_path_dependencies(&lesson).unwrap();
+
+        // Only the path that leaves the lesson changes, and comments stay
+        assert!(manifest.contains(r#"inner = { path = "inner" }"#));
+        assert!(manifest.contains(r#"shared = { path = "deps/shared", features = ["extra"] }"#));
+        assert!(manifest.contains("# Tooling that lives outside the lesson"));
+
+        let locations: Vec<_> = bundled.iter().map(|krate| archive_path(&krate.location)).collect();
+        assert_eq!(locations, ["inner", "deps/shared"]);
+
+        let shared = &bundled[1].manifest;
+        assert!(shared.contains(r#"path = "../..""#));
+        assert!(shared.contains(r#"inner = { path = "../../inner" }"#));
+        assert!(!shared.contains(&temp.path().to_string_lossy().into_owned()));
+    }
+
+    #[test]
+    fn bundled_crates_avoid_name_clashes() {
+        let locations = vec![(PathBuf::from("/a/shared"), PathBuf::from("deps/shared"))];
+        assert_eq!(
+            bundle_location(Path::new("/b/shared"), &locations),
+            Path::new("deps/shared-2")
+        );
+    }
+}
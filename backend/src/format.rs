@@ -0,0 +1,81 @@
+use serde::Serialize;
+use std::fs;
+use tracing::info;
+
+use crate::arch_runner::{
+    prepare_workspace, resolve_lesson, run_command, toolchain_command, COMPILE_TIMEOUT,
+};
+use crate::lessons::DEFAULT_EDITION;
+
+#[derive(Debug, Serialize)]
+pub struct FormatError {
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl From<String> for FormatError {
+    fn from(message: String) -> Self {
+        FormatError {
+            message,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/// Runs rustfmt over a submission inside a runner workspace, using the
+/// lesson's edition so edition-specific syntax parses the same as in cargo.
+pub async fn format_code(code: &str, lesson: Option<&str>) -> Result<String, FormatError> {
+    let lesson = resolve_lesson(lesson)?;
+    let edition = lesson
+        .as_ref()
+        .map_or(DEFAULT_EDITION, |lesson| lesson.edition.as_str());
+    let temp_dir = prepare_workspace(code, lesson.as_ref())?;
+    let lib_path = temp_dir.path().join("src").join("lib.rs");
+
+    info!("Formatting code with edition {}", edition);
+
+    let mut rustfmt = toolchain_command("rustfmt", temp_dir.path());
+    rustfmt.args(["--edition", edition, "src/lib.rs"]);
+    let Some(format_output) = run_command(rustfmt, COMPILE_TIMEOUT).await? else {
+        return Err(FormatError::from(format!(
+            "Rustfmt timed out after {} seconds",
+            COMPILE_TIMEOUT.as_secs()
+        )));
+    };
+
+    if !format_output.status.success() {
+        let stderr = String::from_utf8_lossy(&format_output.stderr);
+        return Err(parse_rustfmt_error(&stderr));
+    }
+
+    fs::read_to_string(&lib_path)
+        .map_err(|e| FormatError::from(format!("Failed to read formatted code: {}", e)))
+}
+
+/// Extracts the first diagnostic from rustfmt's stderr, e.g.
+/// `error: expected expression, found ';'` followed by ` --> src/lib.rs:2:11`.
+fn parse_rustfmt_error(stderr: &str) -> FormatError {
+    let message = stderr
+        .lines()
+        .find_map(|line| line.strip_prefix("error: "))
+        .unwrap_or_else(|| stderr.trim())
+        .to_string();
+
+    let location = stderr
+        .lines()
+        .find_map(|line| line.trim_start().strip_prefix("--> "))
+        .and_then(|location| {
+            let mut parts = location.rsplitn(3, ':');
+            let column = parts.next()?.parse().ok()?;
+            let line = parts.next()?.parse().ok()?;
+            Some((line, column))
+        });
+
+    FormatError {
+        message,
+        line: location.map(|(line, _)| line),
+        column: location.map(|(_, column)| column),
+    }
+}
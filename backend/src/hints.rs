@@ -0,0 +1,423 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use syn::visit::{self, Visit};
+use syn::{
+    Expr, ExprCall, ExprIf, ExprMethodCall, ImplItemFn, Item, ItemEnum, ItemFn, ItemImpl, Member,
+    Type,
+};
+
+use crate::lessons::Lesson;
+
+#[derive(Debug, Serialize)]
+pub struct Hint {
+    /// Lesson whose reference the submission was compared with.
+    pub lesson: String,
+    /// Function or type the hint is about, when it points at one. Methods
+    /// are qualified with their type, e.g. `Mint::unpack_from_slice`.
+    pub item: Option<String>,
+    pub message: String,
+}
+
+impl Hint {
+    fn new(lesson: &Lesson, item: Option<&str>, message: String) -> Self {
+        Hint {
+            lesson: lesson.name.clone(),
+            item: item.map(str::to_string),
+            message,
+        }
+    }
+}
+
+/// Structural facts about one function, compared between the reference
+/// implementation and a submission.
+#[derive(Default)]
+struct FunctionFacts {
+    /// Fields and methods tested in conditions, keyed by member name so
+    /// differently named locals still match, e.g. `is_signer` ->
+    /// `authority_info.is_signer`.
+    checks: BTreeMap<String, String>,
+    /// Paths wrapped in `Err(..)`, e.g. `ProgramError::MissingRequiredSignature`.
+    errors: BTreeSet<String>,
+    /// `checked_*` arithmetic methods.
+    checked_ops: BTreeSet<String>,
+}
+
+#[derive(Default)]
+struct FileFacts {
+    /// Keyed by function name, qualified with the impl's type for methods so
+    /// same-named methods on different types are compared separately.
+    functions: BTreeMap<String, FunctionFacts>,
+    enums: BTreeMap<String, Vec<String>>,
+}
+
+/// Compares a submission with the lesson's reference implementation and
+/// returns targeted hints for anything structurally missing.
+pub fn generate_hints(code: &str, lesson: &Lesson) -> Result<Vec<Hint>, String> {
+    let reference = syn::parse_file(&lesson.reference_code)
+        .map_err(|e| format!("Failed to parse reference code for {}: {}", lesson.name, e))?;
+    let submission = match syn::parse_file(code) {
+        Ok(file) => file,
+        Err(e) => {
+            return Ok(vec![Hint::new(
+                lesson,
+                None,
+                format!("Your code doesn't parse yet: {}", e),
+            )])
+        }
+    };
+
+    let expected = collect_facts(&reference);
+    let actual = collect_facts(&submission);
+    let mut hints = Vec::new();
+
+    for (name, variants) in &expected.enums {
+        let Some(actual_variants) = actual.enums.get(name) else {
+            hints.push(Hint::new(
+                lesson,
+                Some(name),
+                format!("You never define the `{}` enum", name),
+            ));
+            continue;
+        };
+        for variant in variants.iter().filter(|variant| !actual_variants.contains(variant)) {
+            hints.push(Hint::new(
+                lesson,
+                Some(name),
+                format!("`{}` is missing the `{}` variant", name, variant),
+            ));
+        }
+    }
+
+    for (name, expected_fn) in &expected.functions {
+        let Some(actual_fn) = actual.functions.get(name) else {
+            hints.push(Hint::new(
+                lesson,
+                Some(name),
+                format!("You never define `fn {}`", name),
+            ));
+            continue;
+        };
+
+        for (member, check) in &expected_fn.checks {
+            if !actual_fn.checks.contains_key(member) {
+                hints.push(Hint::new(
+                    lesson,
+                    Some(name),
+                    format!("In `{}`, you never check `{}`", name, check),
+                ));
+            }
+        }
+        for error in expected_fn.errors.difference(&actual_fn.errors) {
+            hints.push(Hint::new(
+                lesson,
+                Some(name),
+                format!("`{}` never returns `{}`", name, error),
+            ));
+        }
+        for op in expected_fn.checked_ops.difference(&actual_fn.checked_ops) {
+            hints.push(Hint::new(
+                lesson,
+                Some(name),
+                format!(
+                    "`{}` doesn't use `{}`; unchecked arithmetic can overflow",
+                    name, op
+                ),
+            ));
+        }
+    }
+
+    Ok(hints)
+}
+
+fn collect_facts(file: &syn::File) -> FileFacts {
+    let mut collector = FactCollector::default();
+    collector.visit_file(file);
+    collector.facts
+}
+
+#[derive(Default)]
+struct FactCollector {
+    facts: FileFacts,
+    current: Option<String>,
+    /// Type of the impl block being visited, e.g. `Mint` in `impl Pack for Mint`.
+    impl_type: Option<String>,
+    in_condition: bool,
+}
+
+impl FactCollector {
+    fn visit_function(&mut self, name: String, visit_body: impl FnOnce(&mut Self)) {
+        let name = match &self.impl_type {
+            Some(impl_type) => format!("{}::{}", impl_type, name),
+            None => name,
+        };
+        let previous = self.current.replace(name.clone());
+        self.facts.functions.entry(name).or_default();
+        visit_body(self);
+        self.current = previous;
+    }
+
+    fn current_facts(&mut self) -> Option<&mut FunctionFacts> {
+        let name = self.current.as_ref()?;
+        self.facts.functions.get_mut(name)
+    }
+}
+
+impl<'ast> Visit<'ast> for FactCollector {
+    fn visit_item(&mut self, item: &'ast Item) {
+        // Test modules aren't part of what learners are asked to write
+        if let Item::Mod(module) = item {
+            if module.ident == "tests" {
+                return;
+            }
+        }
+        visit::visit_item(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast ItemEnum) {
+        let variants = item
+            .variants
+            .iter()
+            .map(|variant| variant.ident.to_string())
+            .collect();
+        self.facts.enums.insert(item.ident.to_string(), variants);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast ItemImpl) {
+        let impl_type = match &*item.self_ty {
+            Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()),
+            _ => None,
+        };
+        let previous = std::mem::replace(&mut self.impl_type, impl_type);
+        visit::visit_item_impl(self, item);
+        self.impl_type = previous;
+    }
+
+    fn visit_item_fn(&mut self, item: &'ast ItemFn) {
+        self.visit_function(item.sig.ident.to_string(), |this| {
+            visit::visit_item_fn(this, item)
+        });
+    }
+
+    fn visit_impl_item_fn(&mut self, item: &'ast ImplItemFn) {
+        self.visit_function(item.sig.ident.to_string(), |this| {
+            visit::visit_impl_item_fn(this, item)
+        });
+    }
+
+    fn visit_expr_if(&mut self, expr: &'ast ExprIf) {
+        let was_in_condition = std::mem::replace(&mut self.in_condition, true);
+        self.visit_expr(&expr.cond);
+        self.in_condition = was_in_condition;
+
+        self.visit_block(&expr.then_branch);
+        if let Some((_, else_branch)) = &expr.else_branch {
+            self.visit_expr(else_branch);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        if self.in_condition {
+            let member = match expr {
+                Expr::Field(field) => match &field.member {
+                    Member::Named(ident) => Some(ident.to_string()),
+                    Member::Unnamed(_) => None,
+                },
+                Expr::MethodCall(call) => Some(format!("{}()", call.method)),
+                _ => None,
+            };
+            if let (Some(member), Some(rendered)) = (member, render(expr)) {
+                if let Some(facts) = self.current_facts() {
+                    facts.checks.entry(member).or_insert(rendered);
+                }
+            }
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast ExprCall) {
+        if let (Some(func), Some(arg)) = (render(&call.func), call.args.first()) {
+            if func == "Err" {
+                if let Some(error) = render(arg) {
+                    if let Some(facts) = self.current_facts() {
+                        facts.errors.insert(error);
+                    }
+                }
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        let method = call.method.to_string();
+        if method.starts_with("checked_") {
+            if let Some(facts) = self.current_facts() {
+                facts.checked_ops.insert(method);
+            }
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+/// Renders simple paths, field accesses and zero-argument method calls, e.g.
+/// `authority_info.is_signer` or `mint_info.data_len()`.
+fn render(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => Some(
+            path.path
+                .segments
+                .iter()
+                .map(|segment| segment.ident.to_string())
+                .collect::<Vec<_>>()
+                .join("::"),
+        ),
+        Expr::Field(field) => {
+            let member = match &field.member {
+                Member::Named(ident) => ident.to_string(),
+                Member::Unnamed(index) => index.index.to_string(),
+            };
+            Some(format!("{}.{}", render(&field.base)?, member))
+        }
+        // `Err(TokenError::OwnerMismatch.into())` returns `TokenError::OwnerMismatch`
+        Expr::MethodCall(call) if call.method == "into" && call.args.is_empty() => {
+            render(&call.receiver)
+        }
+        Expr::MethodCall(call) if call.args.is_empty() => {
+            Some(format!("{}.{}()", render(&call.receiver)?, call.method))
+        }
+        Expr::Paren(paren) => render(&paren.expr),
+        Expr::Reference(reference) => render(&reference.expr),
+        Expr::Unary(unary) => render(&unary.expr),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requirements::Requirements;
+    use std::path::PathBuf;
+
+    const REFERENCE: &str = r#"
+        pub enum TokenInstruction { Mint { amount: u64 }, Burn { amount: u64 } }
+
+        pub struct Mint { pub supply: u64 }
+        pub struct Account { pub amount: u64 }
+
+        impl Mint {
+            fn add(&mut self, amount: u64) -> ProgramResult {
+                self.supply = self.supply.checked_add(amount).ok_or(TokenError::Overflow)?;
+                Ok(())
+            }
+        }
+
+        impl Account {
+            fn add(&mut self, amount: u64) -> ProgramResult {
+                if amount == 0 {
+                    return Err(TokenError::ZeroAmount.into());
+                }
+                self.amount += amount;
+                Ok(())
+            }
+        }
+
+        fn process_burn(accounts: &[AccountInfo]) -> ProgramResult {
+            let authority_info = &accounts[0];
+            if !authority_info.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            Ok(())
+        }
+
+        #[cfg(test)]
+        mod tests {
+            fn only_in_tests() {}
+        }
+    "#;
+
+    fn lesson() -> Lesson {
+        Lesson {
+            name: "tokens".to_string(),
+            dir: PathBuf::new(),
+            cargo_toml: String::new(),
+            edition: "2021".to_string(),
+            requirements: Requirements::default(),
+            reference_code: REFERENCE.to_string(),
+            tests: Vec::new(),
+        }
+    }
+
+    fn messages(code: &str) -> Vec<String> {
+        generate_hints(code, &lesson())
+            .unwrap()
+            .into_iter()
+            .map(|hint| hint.message)
+            .collect()
+    }
+
+    #[test]
+    fn reference_needs_no_hints() {
+        assert!(messages(REFERENCE).is_empty());
+    }
+
+    #[test]
+    fn differently_named_locals_still_match() {
+        let code = REFERENCE.replace("authority_info", "owner");
+        assert!(messages(&code).is_empty());
+    }
+
+    #[test]
+    fn missing_items_are_hinted() {
+        let code = r#"
+            pub enum TokenInstruction { Mint { amount: u64 } }
+            fn process_burn(accounts: &[AccountInfo]) -> ProgramResult { Ok(()) }
+        "#;
+
+        assert_eq!(
+            messages(code),
+            [
+                "`TokenInstruction` is missing the `Burn` variant",
+                "You never define `fn Account::add`",
+                "You never define `fn Mint::add`",
+                "In `process_burn`, you never check `authority_info.is_signer`",
+                "`process_burn` never returns `ProgramError::MissingRequiredSignature`",
+            ]
+        );
+    }
+
+    #[test]
+    fn methods_are_compared_per_type() {
+        // Each `add` has what the other's reference asks for, which
+        // doesn't count when methods are told apart by type
+        let code = REFERENCE
+            .replace("self.supply.checked_add(amount).ok_or(TokenError::Overflow)?", "self.supply + amount")
+            .replace("self.amount += amount;", "self.amount = self.amount.checked_add(amount).unwrap();");
+
+        let hints = generate_hints(&code, &lesson()).unwrap();
+
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].lesson, "tokens");
+        assert_eq!(hints[0].item.as_deref(), Some("Mint::add"));
+        assert_eq!(
+            hints[0].message,
+            "`Mint::add` doesn't use `checked_add`; unchecked arithmetic can overflow"
+        );
+    }
+
+    #[test]
+    fn unparsable_submissions_get_one_hint() {
+        let hints = generate_hints("fn process_burn( {", &lesson()).unwrap();
+
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].item, None);
+        assert!(hints[0].message.starts_with("Your code doesn't parse yet"));
+    }
+
+    #[test]
+    fn unparsable_references_are_errors() {
+        let lesson = Lesson {
+            reference_code: "fn broken(".to_string(),
+            ..lesson()
+        };
+        assert!(generate_hints("", &lesson).is_err());
+    }
+}
@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+/// A learner's sandbox chain state, saved between simulations so accounts
+/// created in one step (e.g. a token mint) can be used in the next.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Ledger {
+    pub accounts: Vec<LedgerAccount>,
+    /// Deployments by program id. Simulations always deploy the submission
+    /// at the `program` key, so in practice this is one entry that each
+    /// simulation updates with its lesson.
+    pub programs: Vec<DeployedProgram>,
+}
+
+/// An account as the local validator reports it, with hex keys and data.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LedgerAccount {
+    pub pubkey: String,
+    /// The name a simulation used for this key, e.g. `mint`.
+    #[serde(default)]
+    pub name: Option<String>,
+    pub lamports: u64,
+    pub owner: String,
+    pub data: String,
+    pub is_executable: bool,
+}
+
+/// An account's state after a simulation, as the report's `accounts` diffs
+/// give it. The state before isn't needed to save it.
+#[derive(Deserialize)]
+struct AccountDiff {
+    pubkey: String,
+    name: Option<String>,
+    after: AccountState,
+}
+
+#[derive(Deserialize)]
+struct AccountState {
+    lamports: u64,
+    owner: String,
+    data: String,
+    is_executable: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DeployedProgram {
+    pub program_id: String,
+    pub lesson: Option<String>,
+    /// Unix time of the latest deployment.
+    pub deployed_at: u64,
+}
+
+pub fn ledger_dir() -> PathBuf {
+    env::var("ARCH_LEDGER_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir().join("bitcoin-zombies-ledgers"))
+}
+
+/// Hex keys are the same account whatever their case; the ledger keeps them
+/// lower-case, as the local validator reports them.
+fn normalize_pubkey(pubkey: &str) -> String {
+    pubkey.to_ascii_lowercase()
+}
+
+fn ledger_path(session: &str) -> Result<PathBuf, String> {
+    // Session ids end up in filesystem paths, same rule as lesson names
+    if session.is_empty()
+        || !session
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid session id: {}", session));
+    }
+    Ok(ledger_dir().join(format!("{}.json", session)))
+}
+
+/// Serializes simulations and resets within a session. Hold it from loading
+/// the ledger until saving it, or a concurrent request could load the same
+/// state and its save would drop this one's changes.
+pub async fn lock_session(session: &str) -> OwnedMutexGuard<()> {
+    static LOCKS: OnceLock<Mutex<HashMap<String, Arc<AsyncMutex<()>>>>> = OnceLock::new();
+    let lock = {
+        let mut locks = LOCKS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        // Forget sessions nobody holds or waits on
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(session.to_string()).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+/// Loads a session's ledger; a session that never ran starts empty.
+pub fn load_ledger(session: &str) -> Result<Ledger, String> {
+    let path = ledger_path(session)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => {
+            let mut ledger: Ledger = serde_json::from_str(&contents)
+                .map_err(|e| format!("Failed to parse ledger for session {}: {}", session, e))?;
+            for account in &mut ledger.accounts {
+                account.pubkey = normalize_pubkey(&account.pubkey);
+            }
+            Ok(ledger)
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Ledger::default()),
+        Err(e) => Err(format!("Failed to read ledger for session {}: {}", session, e)),
+    }
+}
+
+pub fn save_ledger(session: &str, ledger: &Ledger) -> Result<(), String> {
+    let path = ledger_path(session)?;
+    fs::create_dir_all(ledger_dir()).map_err(|e| format!("Failed to create ledger directory: {}", e))?;
+    let contents = serde_json::to_string_pretty(ledger)
+        .map_err(|e| format!("Failed to serialize ledger: {}", e))?;
+    // Written aside and renamed into place, so reads never see half a ledger
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, contents)
+        .and_then(|()| fs::rename(&temp_path, &path))
+        .map_err(|e| format!("Failed to write ledger for session {}: {}", session, e))
+}
+
+pub fn reset_ledger(session: &str) -> Result<(), String> {
+    match fs::remove_file(ledger_path(session)?) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to reset ledger for session {}: {}", session, e)),
+    }
+}
+
+impl Ledger {
+    /// Finds an account by hex pubkey or by the name it was created under.
+    pub fn find_account(&self, key: &str) -> Option<&LedgerAccount> {
+        let pubkey = normalize_pubkey(key);
+        self.accounts
+            .iter()
+            .find(|account| account.pubkey == pubkey)
+            .or_else(|| {
+                self.accounts
+                    .iter()
+                    .find(|account| account.name.as_deref() == Some(key))
+            })
+    }
+
+    /// Adds the ledger's accounts to the ones a simulation starts with. An
+    /// account the request already gives wins over the saved one, so a
+    /// learner can try a different state for one simulation; only changes
+    /// the simulation then makes to it are saved back.
+    pub fn preload_accounts(&self, accounts: &mut Vec<LedgerAccount>) {
+        for account in &self.accounts {
+            let overridden = accounts
+                .iter()
+                .any(|given| normalize_pubkey(&given.pubkey) == account.pubkey);
+            if !overridden {
+                accounts.push(account.clone());
+            }
+        }
+    }
+
+    /// Records a simulation: the submission's deployment, and the account
+    /// diffs when the transaction succeeded. Accounts the transaction closed
+    /// are removed. If any diff doesn't parse, none of them are applied.
+    pub fn apply_simulation(&mut self, report: &serde_json::Value, lesson: Option<&str>) -> Result<(), String> {
+        if let Some(program_id) = report.get("program_id").and_then(|id| id.as_str()) {
+            self.record_deployment(program_id, lesson);
+        }
+
+        let succeeded = report
+            .get("success")
+            .and_then(|success| success.as_bool())
+            .unwrap_or(false);
+        let Some(diffs) = report.get("accounts") else {
+            return Ok(());
+        };
+        if !succeeded {
+            return Ok(());
+        }
+        let diffs: Vec<AccountDiff> = serde_json::from_value(diffs.clone())
+            .map_err(|e| format!("Failed to parse account diffs: {}", e))?;
+
+        for diff in diffs {
+            let pubkey = normalize_pubkey(&diff.pubkey);
+            let existing = self.accounts.iter().position(|account| account.pubkey == pubkey);
+            // A closed account has no lamports left and stops existing
+            if diff.after.lamports == 0 {
+                if let Some(index) = existing {
+                    self.accounts.remove(index);
+                }
+                continue;
+            }
+
+            let mut account = LedgerAccount {
+                pubkey,
+                name: diff.name,
+                lamports: diff.after.lamports,
+                owner: diff.after.owner,
+                data: diff.after.data,
+                is_executable: diff.after.is_executable,
+            };
+            match existing {
+                Some(index) => {
+                    let existing = &mut self.accounts[index];
+                    if account.name.is_none() {
+                        account.name = existing.name.take();
+                    }
+                    *existing = account;
+                }
+                None => self.accounts.push(account),
+            }
+        }
+        Ok(())
+    }
+
+    fn record_deployment(&mut self, program_id: &str, lesson: Option<&str>) {
+        let deployed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let lesson = lesson.map(str::to_string);
+        match self
+            .programs
+            .iter_mut()
+            .find(|program| program.program_id == program_id)
+        {
+            Some(program) => {
+                program.lesson = lesson;
+                program.deployed_at = deployed_at;
+            }
+            None => self.programs.push(DeployedProgram {
+                program_id: program_id.to_string(),
+                lesson,
+                deployed_at,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn account(pubkey: &str, name: Option<&str>, lamports: u64) -> LedgerAccount {
+        LedgerAccount {
+            pubkey: pubkey.to_string(),
+            name: name.map(str::to_string),
+            lamports,
+            owner: "00".repeat(32),
+            data: String::new(),
+            is_executable: false,
+        }
+    }
+
+    fn report(success: bool, diffs: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "program_id": "ab".repeat(32),
+            "success": success,
+            "accounts": diffs,
+        })
+    }
+
+    fn diff(pubkey: &str, name: Option<&str>, lamports: u64) -> serde_json::Value {
+        serde_json::json!({
+            "pubkey": pubkey,
+            "name": name,
+            "before": null,
+            "after": {
+                "lamports": lamports,
+                "owner": "00".repeat(32),
+                "data": "0102",
+                "is_executable": false,
+            },
+        })
+    }
+
+    #[test]
+    fn successful_simulations_save_their_diffs() {
+        let mut ledger = Ledger::default();
+        let mint = "11".repeat(32);
+
+        ledger
+            .apply_simulation(&report(true, serde_json::json!([diff(&mint, Some("mint"), 5)])), Some("tokens"))
+            .unwrap();
+        // A later diff without a name keeps the name the account was created under
+        ledger
+            .apply_simulation(&report(true, serde_json::json!([diff(&mint, None, 7)])), Some("tokens"))
+            .unwrap();
+
+        assert_eq!(ledger.accounts.len(), 1);
+        let saved = ledger.find_account("mint").unwrap();
+        assert_eq!((saved.pubkey.as_str(), saved.lamports, saved.data.as_str()), (mint.as_str(), 7, "0102"));
+        assert_eq!(ledger.programs.len(), 1);
+        assert_eq!(ledger.programs[0].lesson.as_deref(), Some("tokens"));
+    }
+
+    #[test]
+    fn failed_simulations_only_record_the_deployment() {
+        let mut ledger = Ledger::default();
+
+        ledger
+            .apply_simulation(&report(false, serde_json::json!([diff(&"11".repeat(32), None, 5)])), None)
+            .unwrap();
+
+        assert!(ledger.accounts.is_empty());
+        assert_eq!(ledger.programs.len(), 1);
+    }
+
+    #[test]
+    fn malformed_diffs_are_rejected() {
+        for malformed in [
+            serde_json::json!({ "pubkey": "11".repeat(32) }),
+            serde_json::json!({ "after": { "lamports": 1 } }),
+            serde_json::json!({ "pubkey": "22".repeat(32), "after": { "lamports": "many" } }),
+        ] {
+            let mut ledger = Ledger::default();
+            let diffs = serde_json::json!([diff(&"33".repeat(32), None, 5), malformed]);
+
+            let err = ledger.apply_simulation(&report(true, diffs), None).unwrap_err();
+
+            assert!(err.starts_with("Failed to parse account diffs"), "{}", err);
+            // Not even the diffs that did parse are applied
+            assert!(ledger.accounts.is_empty());
+        }
+    }
+
+    #[test]
+    fn closed_accounts_are_removed() {
+        let mut ledger = Ledger {
+            accounts: vec![account(&"aa".repeat(32), Some("mint"), 5), account(&"bb".repeat(32), None, 6)],
+            programs: Vec::new(),
+        };
+
+        // The validator reports a closed account as an empty one
+        let mut closed = diff(&"aa".repeat(32), None, 0);
+        closed["after"]["data"] = serde_json::json!("");
+        ledger.apply_simulation(&report(true, serde_json::json!([closed])), None).unwrap();
+
+        assert!(ledger.find_account("mint").is_none());
+        assert_eq!(ledger.accounts.len(), 1);
+        assert!(ledger.find_account(&"bb".repeat(32)).is_some());
+    }
+
+    #[test]
+    fn pubkeys_match_whatever_their_case() {
+        let session = format!("test-{}-case", std::process::id());
+        // Saved before keys were normalized
+        let ledger = Ledger {
+            accounts: vec![account(&"AA".repeat(32), Some("mint"), 5)],
+            programs: Vec::new(),
+        };
+        save_ledger(&session, &ledger).unwrap();
+        let mut ledger = load_ledger(&session).unwrap();
+        reset_ledger(&session).unwrap();
+
+        let mut accounts = Vec::new();
+        ledger.preload_accounts(&mut accounts);
+        assert_eq!(accounts[0].pubkey, "aa".repeat(32));
+
+        // A simulation's diff updates the saved account rather than adding
+        // a second copy of it
+        ledger
+            .apply_simulation(&report(true, serde_json::json!([diff(&"aA".repeat(32), None, 7)])), None)
+            .unwrap();
+        assert_eq!(ledger.accounts.len(), 1);
+        assert_eq!(ledger.find_account(&"Aa".repeat(32)).map(|account| account.lamports), Some(7));
+        assert_eq!(ledger.find_account("mint").map(|account| account.lamports), Some(7));
+    }
+
+    #[test]
+    fn request_accounts_take_precedence_over_saved_ones() {
+        let ledger = Ledger {
+            accounts: vec![account(&"aa".repeat(32), Some("mint"), 5), account(&"bb".repeat(32), None, 6)],
+            programs: Vec::new(),
+        };
+        let mut accounts = vec![account(&"AA".repeat(32), None, 100)];
+
+        ledger.preload_accounts(&mut accounts);
+
+        let lamports: Vec<u64> = accounts.iter().map(|account| account.lamports).collect();
+        assert_eq!(lamports, [100, 6]);
+    }
+
+    #[test]
+    fn session_ids_must_be_path_safe() {
+        assert!(ledger_path("learner-1_a").is_ok());
+        for session in ["", "../etc", "a/b", "a.json"] {
+            assert!(ledger_path(session).is_err(), "{}", session);
+        }
+    }
+
+    #[test]
+    fn ledgers_round_trip_through_disk() {
+        let session = format!("test-{}-round-trip", std::process::id());
+        let mut ledger = Ledger::default();
+        ledger.accounts.push(account(&"aa".repeat(32), Some("mint"), 5));
+
+        save_ledger(&session, &ledger).unwrap();
+        let loaded = load_ledger(&session).unwrap();
+        reset_ledger(&session).unwrap();
+
+        assert_eq!(loaded.find_account("mint").map(|account| account.lamports), Some(5));
+        assert!(load_ledger(&session).unwrap().accounts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sessions_are_locked_one_request_at_a_time() {
+        let session = format!("test-{}-lock", std::process::id());
+        let guard = lock_session(&session).await;
+
+        let waiting = tokio::time::timeout(Duration::from_millis(50), lock_session(&session)).await;
+        assert!(waiting.is_err());
+        // Other sessions aren't held up
+        let other = format!("{}-other", session);
+        tokio::time::timeout(Duration::from_millis(50), lock_session(&other))
+            .await
+            .unwrap();
+
+        drop(guard);
+        tokio::time::timeout(Duration::from_millis(50), lock_session(&session))
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::requirements::Requirements;
+
+/// Edition used when a lesson does not pin one, matching the runner's default crate.
+pub const DEFAULT_EDITION: &str = "2021";
+
+/// A lesson crate from `arch-lessons/`, used to seed runner workspaces.
+pub struct Lesson {
+    pub name: String,
+    /// The lesson crate's directory, for anything that needs its files.
+    pub dir: PathBuf,
+    /// The lesson's Cargo.toml with path dependencies made absolute.
+    pub cargo_toml: String,
+    /// Rust edition from the lesson's `[package]` table.
+    pub edition: String,
+    /// Structural checks from `[package.metadata.zombies]`.
+    pub requirements: Requirements,
+    /// The lesson's reference implementation from `src/lib.rs`.
+    pub reference_code: String,
+    /// Integration tests as `(file name, contents)` pairs.
+    pub tests: Vec<(String, String)>,
+}
+
+pub fn lessons_dir() -> PathBuf {
+    env::var("ARCH_LESSONS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("../arch-lessons"))
+}
+
+pub fn load_lesson(name: &str) -> Result<Lesson, String> {
+    // Lesson names end up in filesystem paths, so only allow plain crate names
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid lesson name: {}", name));
+    }
+
+    let dir = lessons_dir().join(name);
+    let mut cargo_toml = fs::read_to_string(dir.join("Cargo.toml"))
+        .map_err(|e| format!("Failed to read Cargo.toml for lesson {}: {}", name, e))?;
+    let mut manifest: toml::Table = cargo_toml
+        .parse()
+        .map_err(|e| format!("Failed to parse Cargo.toml for lesson {}: {}", name, e))?;
+    // Workspaces live in a temp directory, where relative paths don't resolve
+    if absolutize_path_dependencies(&mut manifest, &dir) {
+        cargo_toml = toml::to_string(&manifest)
+            .map_err(|e| format!("Failed to write Cargo.toml for lesson {}: {}", name, e))?;
+    }
+    let edition = manifest
+        .get("package")
+        .and_then(|package| package.get("edition"))
+        .and_then(|edition| edition.as_str())
+        .unwrap_or(DEFAULT_EDITION)
+        .to_string();
+    let requirements = manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("zombies"))
+        .cloned()
+        .map(|zombies| zombies.try_into())
+        .transpose()
+        .map_err(|e| format!("Invalid lesson metadata for {}: {}", name, e))?
+        .unwrap_or_default();
+
+    let reference_code = fs::read_to_string(dir.join("src").join("lib.rs"))
+        .map_err(|e| format!("Failed to read reference code for lesson {}: {}", name, e))?;
+
+    let mut tests = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir.join("tests")) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
+                continue;
+            }
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read test {}: {}", file_name, e))?;
+            tests.push((file_name, contents));
+        }
+    }
+    tests.sort();
+
+    Ok(Lesson {
+        name: name.to_string(),
+        dir,
+        cargo_toml,
+        edition,
+        requirements,
+        reference_code,
+        tests,
+    })
+}
+
+/// Rewrites relative `path` dependencies to absolute paths under `dir`.
+/// Returns whether anything changed.
+fn absolutize_path_dependencies(manifest: &mut toml::Table, dir: &Path) -> bool {
+    let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    let mut changed = false;
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        let Some(dependencies) = manifest.get_mut(section).and_then(|deps| deps.as_table_mut())
+        else {
+            continue;
+        };
+        for (_, dependency) in dependencies.iter_mut() {
+            let Some(path) = dependency.get_mut("path") else {
+                continue;
+            };
+            let Some(relative) = path.as_str().filter(|path| Path::new(path).is_relative()) else {
+                continue;
+            };
+            let absolute = dir.join(relative);
+            let absolute = fs::canonicalize(&absolute).unwrap_or(absolute);
+            *path = toml::Value::String(absolute.to_string_lossy().into_owned());
+            changed = true;
+        }
+    }
+    changed
+}
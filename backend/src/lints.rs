@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::arch_runner::{
+    cargo_command, prepare_workspace, resolve_lesson, run_command, COMPILE_TIMEOUT,
+};
+
+#[derive(Debug, Serialize)]
+pub struct Lint {
+    /// Lint name, e.g. `clippy::unwrap_used` or `unused_variables`.
+    pub code: String,
+    pub level: String,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub help: Vec<String>,
+}
+
+impl Lint {
+    pub fn is_denied(&self) -> bool {
+        self.level == "error"
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct Diagnostic {
+    code: Option<DiagnosticCode>,
+    level: String,
+    message: String,
+    #[serde(default)]
+    spans: Vec<DiagnosticSpan>,
+    #[serde(default)]
+    children: Vec<Diagnostic>,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct DiagnosticSpan {
+    file_name: String,
+    line_start: usize,
+    column_start: usize,
+    is_primary: bool,
+}
+
+/// Runs clippy over a submission. Lesson policies come from the lesson's
+/// `[lints.clippy]` table, which cargo applies because the runner workspace
+/// reuses the lesson's Cargo.toml.
+pub async fn lint_code(code: &str, lesson: Option<&str>) -> Result<Vec<Lint>, String> {
+    let lesson = resolve_lesson(lesson)?;
+    let temp_dir = prepare_workspace(code, lesson.as_ref())?;
+
+    info!("Running clippy on submission");
+
+    let mut clippy = cargo_command(temp_dir.path());
+    clippy.args(["clippy", "--lib", "--message-format=json"]);
+    let Some(clippy_output) = run_command(clippy, COMPILE_TIMEOUT).await? else {
+        return Err(format!(
+            "Clippy timed out after {} seconds",
+            COMPILE_TIMEOUT.as_secs()
+        ));
+    };
+
+    let stdout = String::from_utf8_lossy(&clippy_output.stdout);
+    let lints = parse_lints(&stdout);
+
+    // Clippy exits non-zero for denied lints too; only treat it as a failure
+    // when it produced nothing we can show
+    if !clippy_output.status.success() && lints.is_empty() {
+        let stderr = String::from_utf8_lossy(&clippy_output.stderr);
+        return Err(format!("Clippy failed:\n{}", stderr));
+    }
+
+    Ok(lints)
+}
+
+fn parse_lints(stdout: &str) -> Vec<Lint> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|message| message.reason == "compiler-message")
+        .filter_map(|message| message.message)
+        .filter_map(|diagnostic| {
+            let code = diagnostic.code?.code;
+            // Error codes like E0425 are compile errors, which the run itself reports
+            if is_error_code(&code) {
+                return None;
+            }
+            let span = diagnostic
+                .spans
+                .iter()
+                .find(|span| span.is_primary && span.file_name == "src/lib.rs");
+            Some(Lint {
+                code,
+                level: diagnostic.level,
+                message: diagnostic.message,
+                line: span.map(|span| span.line_start),
+                column: span.map(|span| span.column_start),
+                help: diagnostic
+                    .children
+                    .into_iter()
+                    .filter(|child| child.level == "help")
+                    .map(|child| child.message)
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+fn is_error_code(code: &str) -> bool {
+    code.strip_prefix('E')
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One line of `cargo clippy --message-format=json` output.
+    fn compiler_message(code: Option<&str>, level: &str, message: &str, file: &str) -> String {
+        serde_json::json!({
+            "reason": "compiler-message",
+            "message": {
+                "code": code.map(|code| serde_json::json!({ "code": code })),
+                "level": level,
+                "message": message,
+                "spans": [
+                    { "file_name": file, "line_start": 4, "column_start": 13, "is_primary": true }
+                ],
+                "children": [
+                    { "code": null, "level": "note", "message": "`#[deny(clippy::unwrap_used)]` on by default", "spans": [], "children": [] },
+                    { "code": null, "level": "help", "message": "use `?` instead", "spans": [], "children": [] }
+                ]
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn lints_are_read_from_compiler_messages() {
+        let stdout = [
+            r#"{"reason":"compiler-artifact","target":{"name":"lesson"}}"#.to_string(),
+            compiler_message(
+                Some("clippy::unwrap_used"),
+                "error",
+                "used `unwrap()` on a `Result` value",
+                "src/lib.rs",
+            ),
+            compiler_message(
+                Some("unused_variables"),
+                "warning",
+                "unused variable: `x`",
+                "src/lib.rs",
+            ),
+            r#"{"reason":"build-finished","success":false}"#.to_string(),
+        ]
+        .join("\n");
+
+        let lints = parse_lints(&stdout);
+
+        assert_eq!(lints.len(), 2);
+        assert_eq!(lints[0].code, "clippy::unwrap_used");
+        assert!(lints[0].is_denied());
+        assert_eq!((lints[0].line, lints[0].column), (Some(4), Some(13)));
+        assert_eq!(lints[0].help, ["use `?` instead"]);
+        assert_eq!(lints[1].code, "unused_variables");
+        assert!(!lints[1].is_denied());
+    }
+
+    #[test]
+    fn compile_errors_and_uncoded_messages_are_skipped() {
+        let stdout = [
+            compiler_message(
+                Some("E0425"),
+                "error",
+                "cannot find value `y` in this scope",
+                "src/lib.rs",
+            ),
+            compiler_message(
+                None,
+                "error",
+                "aborting due to 1 previous error",
+                "src/lib.rs",
+            ),
+        ]
+        .join("\n");
+
+        assert!(parse_lints(&stdout).is_empty());
+    }
+
+    #[test]
+    fn lints_outside_the_submission_have_no_location() {
+        let stdout = compiler_message(
+            Some("clippy::unwrap_used"),
+            "error",
+            "used `unwrap()`",
+            "src/harness.rs",
+        );
+
+        let lints = parse_lints(&stdout);
+
+        assert_eq!(lints.len(), 1);
+        assert_eq!((lints[0].line, lints[0].column), (None, None));
+    }
+
+    #[test]
+    fn malformed_lines_are_ignored() {
+        let stdout = "warning: build script output\nnot json\n{\"reason\":\"compiler-message\"}";
+        assert!(parse_lints(stdout).is_empty());
+    }
+
+    #[test]
+    fn only_numbered_codes_are_compile_errors() {
+        assert!(is_error_code("E0425"));
+        assert!(!is_error_code("E"));
+        assert!(!is_error_code("Ex01"));
+        assert!(!is_error_code("clippy::unwrap_used"));
+    }
+}
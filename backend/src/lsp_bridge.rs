@@ -0,0 +1,182 @@
+use axum::extract::ws::{Message, WebSocket};
+use std::process::Stdio;
+use tempfile::TempDir;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::arch_runner::{
+    cargo_command, prepare_workspace, resolve_lesson, run_command, toolchain_command,
+    COMPILE_TIMEOUT,
+};
+
+/// Root URI the browser editor uses for documents. The bridge swaps it for
+/// the session workspace path so the client never needs to know where that is.
+pub const CLIENT_ROOT_URI: &str = "file:///workspace";
+
+/// A rust-analyzer process running against a per-session workspace that has
+/// the lesson's Cargo.toml, so `arch_program::` APIs resolve for completions.
+pub struct LspSession {
+    // Held so the workspace outlives the language server
+    _workspace: TempDir,
+    root_uri: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl LspSession {
+    pub async fn start(lesson: Option<&str>) -> Result<Self, String> {
+        let lesson = resolve_lesson(lesson)?;
+        let workspace = prepare_workspace("", lesson.as_ref())?;
+        let root_uri = format!("file://{}", workspace.path().display());
+
+        info!("Fetching dependencies for language server workspace");
+        let mut fetch = cargo_command(workspace.path());
+        fetch.arg("fetch");
+        // rust-analyzer still works for std and the submission itself, so a
+        // failed fetch only costs completions for dependencies
+        match run_command(fetch, COMPILE_TIMEOUT).await? {
+            Some(fetch_output) if fetch_output.status.success() => {}
+            Some(fetch_output) => warn!(
+                "cargo fetch failed: {}",
+                String::from_utf8_lossy(&fetch_output.stderr)
+            ),
+            None => warn!(
+                "cargo fetch timed out after {} seconds",
+                COMPILE_TIMEOUT.as_secs()
+            ),
+        }
+
+        let mut child = Command::from(toolchain_command("rust-analyzer", workspace.path()))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to start rust-analyzer: {}", e))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| "Failed to open rust-analyzer stdin".to_string())?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| "Failed to open rust-analyzer stdout".to_string())?;
+
+        Ok(LspSession {
+            _workspace: workspace,
+            root_uri,
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Relays JSON-RPC messages between the socket and rust-analyzer until
+    /// either side closes. Each WebSocket text frame carries one message body.
+    pub async fn bridge(self, mut socket: WebSocket) {
+        let LspSession {
+            _workspace,
+            root_uri,
+            mut child,
+            mut stdin,
+            mut stdout,
+        } = self;
+
+        // Framed reads are not cancel-safe, so they get their own task
+        let (sender, mut receiver) = mpsc::channel(32);
+        let reader = tokio::spawn(async move {
+            loop {
+                let message = read_message(&mut stdout).await;
+                let done = !matches!(message, Ok(Some(_)));
+                if sender.send(message).await.is_err() || done {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            tokio::select! {
+                incoming = socket.recv() => {
+                    let text = match incoming {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => {
+                            warn!("Language server socket error: {}", e);
+                            break;
+                        }
+                    };
+                    let body = text.replace(CLIENT_ROOT_URI, &root_uri);
+                    if let Err(e) = write_message(&mut stdin, &body).await {
+                        warn!("Failed to write to rust-analyzer: {}", e);
+                        break;
+                    }
+                }
+                outgoing = receiver.recv() => {
+                    let body = match outgoing {
+                        Some(Ok(Some(body))) => body,
+                        Some(Ok(None)) | None => break,
+                        Some(Err(e)) => {
+                            warn!("Failed to read from rust-analyzer: {}", e);
+                            break;
+                        }
+                    };
+                    let text = body.replace(&root_uri, CLIENT_ROOT_URI);
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        info!("Language server session closed");
+        reader.abort();
+        let _ = child.kill().await;
+    }
+}
+
+async fn write_message(stdin: &mut ChildStdin, body: &str) -> std::io::Result<()> {
+    let frame = format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+    stdin.write_all(frame.as_bytes()).await?;
+    stdin.flush().await
+}
+
+/// Reads one `Content-Length` framed message, returning `None` at EOF.
+async fn read_message(stdout: &mut BufReader<ChildStdout>) -> Result<Option<String>, String> {
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        let read = stdout
+            .read_line(&mut header)
+            .await
+            .map_err(|e| format!("Failed to read header: {}", e))?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(length) = header.strip_prefix("Content-Length: ") {
+            content_length = Some(
+                length
+                    .parse::<usize>()
+                    .map_err(|e| format!("Invalid Content-Length: {}", e))?,
+            );
+        }
+    }
+
+    let length = content_length.ok_or_else(|| "Missing Content-Length header".to_string())?;
+    let mut body = vec![0; length];
+    stdout
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| format!("Failed to read body: {}", e))?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|e| format!("Invalid UTF-8 in message: {}", e))
+}
@@ -1,7 +1,7 @@
 use axum::{
-    extract::Json,
-    http::StatusCode,
-    response::Json as ResponseJson,
+    extract::{ws::WebSocketUpgrade, Json, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json as ResponseJson, Response},
     routing::post,
     Router,
 };
@@ -11,10 +11,44 @@ use tower_http::cors::CorsLayer;
 use tracing::{info, error};
 
 mod arch_runner;
+mod export;
+mod format;
+mod hints;
+mod ledger;
+mod lessons;
+mod lints;
+mod lsp_bridge;
+mod metering;
+mod pda;
+mod requirements;
+mod runs;
+mod simulate;
 
 #[derive(Deserialize)]
 struct RunCodeRequest {
     code: String,
+    lesson: Option<String>,
+    /// Run clippy with the lesson's lint policy before executing.
+    #[serde(default)]
+    lint: bool,
+    #[serde(default)]
+    instruction_data: Vec<u8>,
+    compute_budget: Option<u64>,
+}
+
+impl RunCodeRequest {
+    /// Execution inputs, with the compute budget clamped to the most a
+    /// transaction may use so a request can't switch metering off.
+    fn run_options(&self) -> arch_runner::RunOptions {
+        let defaults = arch_runner::RunOptions::default();
+        arch_runner::RunOptions {
+            instruction_data: self.instruction_data.clone(),
+            compute_budget: self
+                .compute_budget
+                .unwrap_or(defaults.compute_budget)
+                .min(metering::MAX_COMPUTE_BUDGET),
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -22,31 +56,516 @@ struct RunCodeResponse {
     output: Option<String>,
     error: Option<String>,
     success: bool,
+    lints: Option<Vec<lints::Lint>>,
+    hints: Option<Vec<hints::Hint>>,
+    /// Id for replaying this run with `/api/runs/:id/replay`.
+    run_id: Option<String>,
 }
 
 async fn run_arch_code(Json(request): Json<RunCodeRequest>) -> Result<ResponseJson<RunCodeResponse>, StatusCode> {
     info!("Received code execution request");
     
-    match arch_runner::compile_and_run(&request.code).await {
+    let started_at = runs::unix_time();
+    let mut response = execute_run(&request).await;
+    let record = runs::RunRecord::new(
+        &request.code,
+        request.lesson.as_deref(),
+        started_at,
+        runs::RunInputs::Run {
+            lint: request.lint,
+            instruction_data: request.instruction_data.clone(),
+            compute_budget: request.run_options().compute_budget,
+        },
+        runs::RunOutcome {
+            success: response.success,
+            output: response.output.clone(),
+            error: response.error.clone(),
+            simulation: None,
+        },
+    );
+    response.run_id = save_run(&record);
+    Ok(ResponseJson(response))
+}
+
+/// Records a run for replay; a recording failure never fails the run itself.
+fn save_run(record: &runs::RunRecord) -> Option<String> {
+    match runs::save_run(record) {
+        Ok(()) => Some(record.id.clone()),
+        Err(err) => {
+            error!("Failed to record run: {}", err);
+            None
+        }
+    }
+}
+
+async fn execute_run(request: &RunCodeRequest) -> RunCodeResponse {
+    let lints = if request.lint {
+        match lints::lint_code(&request.code, request.lesson.as_deref()).await {
+            Ok(lints) => Some(lints),
+            Err(err) => {
+                error!("Linting failed: {}", err);
+                return RunCodeResponse {
+                    output: None,
+                    error: Some(err),
+                    success: false,
+                    lints: None,
+                    hints: None,
+                    run_id: None,
+                };
+            }
+        }
+    } else {
+        None
+    };
+    
+    if let Some(denied) = lints
+        .as_ref()
+        .map(|lints| lints.iter().filter(|lint| lint.is_denied()).count())
+        .filter(|denied| *denied > 0)
+    {
+        info!("Submission rejected by lint policy");
+        return RunCodeResponse {
+            output: None,
+            error: Some(format!("{} lint(s) denied by the lesson policy", denied)),
+            success: false,
+            lints,
+            hints: None,
+            run_id: None,
+        };
+    }
+    
+    match arch_runner::compile_and_run(&request.code, request.lesson.as_deref(), &request.run_options()).await {
         Ok(output) => {
             info!("Code executed successfully");
-            Ok(ResponseJson(RunCodeResponse {
+            RunCodeResponse {
                 output: Some(output),
                 error: None,
                 success: true,
-            }))
+                lints,
+                hints: None,
+                run_id: None,
+            }
         }
         Err(err) => {
             error!("Code execution failed: {}", err);
-            Ok(ResponseJson(RunCodeResponse {
+            let hints = lesson_hints(&request.code, request.lesson.as_deref());
+            RunCodeResponse {
                 output: None,
                 error: Some(err),
                 success: false,
+                lints,
+                hints,
+                run_id: None,
+            }
+        }
+    }
+}
+
+/// Hints for a failed lesson run; hint errors never mask the run's own error.
+fn lesson_hints(code: &str, lesson: Option<&str>) -> Option<Vec<hints::Hint>> {
+    let lesson = arch_runner::resolve_lesson(lesson).ok()??;
+    match hints::generate_hints(code, &lesson) {
+        Ok(hints) => Some(hints),
+        Err(err) => {
+            error!("Hint generation failed: {}", err);
+            None
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HintsResponse {
+    hints: Vec<hints::Hint>,
+    error: Option<String>,
+    success: bool,
+}
+
+async fn get_hints(Json(request): Json<RunCodeRequest>) -> Result<ResponseJson<HintsResponse>, StatusCode> {
+    info!("Received hints request");
+    
+    let Some(lesson) = request.lesson.as_deref() else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    
+    let result = lessons::load_lesson(lesson)
+        .and_then(|lesson| hints::generate_hints(&request.code, &lesson));
+    
+    match result {
+        Ok(hints) => Ok(ResponseJson(HintsResponse {
+            hints,
+            error: None,
+            success: true,
+        })),
+        Err(err) => {
+            error!("Hint generation failed: {}", err);
+            Ok(ResponseJson(HintsResponse {
+                hints: Vec::new(),
+                error: Some(err),
+                success: false,
             }))
         }
     }
 }
 
+#[derive(Serialize)]
+struct FormatCodeResponse {
+    formatted: Option<String>,
+    error: Option<format::FormatError>,
+    success: bool,
+}
+
+async fn format_code(Json(request): Json<RunCodeRequest>) -> Result<ResponseJson<FormatCodeResponse>, StatusCode> {
+    info!("Received code format request");
+    
+    match format::format_code(&request.code, request.lesson.as_deref()).await {
+        Ok(formatted) => Ok(ResponseJson(FormatCodeResponse {
+            formatted: Some(formatted),
+            error: None,
+            success: true,
+        })),
+        Err(err) => {
+            error!("Code formatting failed: {}", err.message);
+            Ok(ResponseJson(FormatCodeResponse {
+                formatted: None,
+                error: Some(err),
+                success: false,
+            }))
+        }
+    }
+}
+
+async fn export_project(Json(request): Json<RunCodeRequest>) -> Response {
+    info!("Received project export request");
+    
+    match export::export_project(&request.code, request.lesson.as_deref()).await {
+        Ok(archive) => {
+            let file_name = request.lesson.as_deref().unwrap_or("arch_program");
+            (
+                [
+                    (header::CONTENT_TYPE, "application/zip".to_string()),
+                    (
+                        header::CONTENT_DISPOSITION,
+                        format!("attachment; filename=\"{}.zip\"", file_name),
+                    ),
+                ],
+                archive,
+            )
+                .into_response()
+        }
+        Err(err) => {
+            error!("Project export failed: {}", err);
+            (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ResponseJson(RunCodeResponse {
+                    output: None,
+                    error: Some(err),
+                    success: false,
+                    lints: None,
+                    hints: None,
+                    run_id: None,
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SimulateRequest {
+    code: String,
+    lesson: Option<String>,
+    /// Runs against this session's saved ledger and saves the result. The
+    /// transaction's own `accounts` take precedence over saved ones.
+    session: Option<String>,
+    #[serde(flatten)]
+    transaction: simulate::SimulationSpec,
+}
+
+#[derive(Serialize)]
+struct SimulateResponse {
+    simulation: Option<serde_json::Value>,
+    error: Option<String>,
+    success: bool,
+    run_id: Option<String>,
+}
+
+async fn simulate_transaction(Json(mut request): Json<SimulateRequest>) -> Result<ResponseJson<SimulateResponse>, StatusCode> {
+    info!("Received transaction simulation request");
+    
+    let started_at = runs::unix_time();
+    let result = match request.session.take() {
+        Some(session) => simulate_in_session(&session, &mut request).await,
+        None => simulate::simulate(&request.code, request.lesson.as_deref(), &request.transaction).await,
+    };
+    
+    let mut response = simulate_response(result);
+    let record = runs::RunRecord::new(
+        &request.code,
+        request.lesson.as_deref(),
+        started_at,
+        runs::RunInputs::Simulate {
+            transaction: request.transaction,
+        },
+        runs::RunOutcome {
+            success: response.success,
+            output: None,
+            error: response.error.clone(),
+            simulation: response.simulation.clone(),
+        },
+    );
+    response.run_id = save_run(&record);
+    Ok(ResponseJson(response))
+}
+
+fn simulate_response(result: Result<serde_json::Value, String>) -> SimulateResponse {
+    match result {
+        Ok(simulation) => {
+            // The report's own `success` says whether the transaction went through
+            let success = simulation
+                .get("success")
+                .and_then(|success| success.as_bool())
+                .unwrap_or(false);
+            let error = simulation
+                .get("error")
+                .and_then(|error| error.as_str())
+                .map(str::to_string);
+            SimulateResponse {
+                simulation: Some(simulation),
+                error,
+                success,
+                run_id: None,
+            }
+        }
+        Err(err) => {
+            error!("Transaction simulation failed: {}", err);
+            SimulateResponse {
+                simulation: None,
+                error: Some(err),
+                success: false,
+                run_id: None,
+            }
+        }
+    }
+}
+
+async fn simulate_in_session(session: &str, request: &mut SimulateRequest) -> Result<serde_json::Value, String> {
+    let _lock = ledger::lock_session(session).await;
+    let mut ledger = ledger::load_ledger(session)?;
+    ledger.preload_accounts(&mut request.transaction.accounts);
+    
+    let simulation = simulate::simulate(&request.code, request.lesson.as_deref(), &request.transaction).await?;
+    ledger.apply_simulation(&simulation, request.lesson.as_deref())?;
+    ledger::save_ledger(session, &ledger)?;
+    Ok(simulation)
+}
+
+#[derive(Serialize)]
+struct LedgerResponse {
+    accounts: Vec<AccountSummary>,
+    programs: Vec<ledger::DeployedProgram>,
+}
+
+/// An account listing entry; fetch the account itself for its data.
+#[derive(Serialize)]
+struct AccountSummary {
+    pubkey: String,
+    name: Option<String>,
+    lamports: u64,
+    owner: String,
+    data_len: usize,
+    is_executable: bool,
+}
+
+async fn list_session_accounts(Path(session): Path<String>) -> Result<ResponseJson<LedgerResponse>, StatusCode> {
+    let ledger = ledger::load_ledger(&session).map_err(|err| {
+        error!("Failed to load ledger: {}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    
+    let accounts = ledger
+        .accounts
+        .into_iter()
+        .map(|account| AccountSummary {
+            data_len: account.data.len() / 2,
+            pubkey: account.pubkey,
+            name: account.name,
+            lamports: account.lamports,
+            owner: account.owner,
+            is_executable: account.is_executable,
+        })
+        .collect();
+    
+    Ok(ResponseJson(LedgerResponse {
+        accounts,
+        programs: ledger.programs,
+    }))
+}
+
+async fn get_session_account(Path((session, key)): Path<(String, String)>) -> Result<ResponseJson<ledger::LedgerAccount>, StatusCode> {
+    let ledger = ledger::load_ledger(&session).map_err(|err| {
+        error!("Failed to load ledger: {}", err);
+        StatusCode::BAD_REQUEST
+    })?;
+    
+    ledger
+        .find_account(&key)
+        .cloned()
+        .map(ResponseJson)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn reset_session(Path(session): Path<String>) -> StatusCode {
+    info!("Resetting ledger for session {}", session);
+    
+    let _lock = ledger::lock_session(&session).await;
+    match ledger::reset_ledger(&session) {
+        Ok(()) => StatusCode::NO_CONTENT,
+        Err(err) => {
+            error!("Failed to reset ledger: {}", err);
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PdaRequest {
+    /// Hex program id the address is derived for.
+    program_id: String,
+    seeds: Vec<pda::Seed>,
+    /// Check a known bump with `create_program_address` instead of searching.
+    bump: Option<u8>,
+}
+
+#[derive(Serialize)]
+struct PdaResponse {
+    pda: Option<pda::ProgramAddress>,
+    error: Option<String>,
+    success: bool,
+}
+
+async fn derive_program_address(Json(request): Json<PdaRequest>) -> Result<ResponseJson<PdaResponse>, StatusCode> {
+    info!("Received program address request");
+    
+    let result = match request.bump {
+        Some(bump) => pda::create_program_address(&request.seeds, bump, &request.program_id),
+        None => pda::find_program_address(&request.seeds, &request.program_id),
+    };
+    
+    match result {
+        Ok(pda) => Ok(ResponseJson(PdaResponse {
+            pda: Some(pda),
+            error: None,
+            success: true,
+        })),
+        Err(err) => Ok(ResponseJson(PdaResponse {
+            pda: None,
+            error: Some(err),
+            success: false,
+        })),
+    }
+}
+
+async fn get_run(Path(id): Path<String>) -> Result<ResponseJson<runs::RunRecord>, StatusCode> {
+    load_run(&id).map(ResponseJson)
+}
+
+fn load_run(id: &str) -> Result<runs::RunRecord, StatusCode> {
+    match runs::load_run(id) {
+        Ok(Some(record)) => Ok(record),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(err) => {
+            error!("Failed to load run: {}", err);
+            Err(StatusCode::BAD_REQUEST)
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReplayResponse {
+    run_id: String,
+    /// Unix time the recorded run started.
+    started_at: u64,
+    recorded: runs::RunOutcome,
+    replayed: runs::RunOutcome,
+    /// Whether the replay reproduced the recorded outcome exactly.
+    identical: bool,
+    /// Toolchain or lesson changes since the recording that may explain a difference.
+    environment_changes: Vec<String>,
+}
+
+async fn replay_run(Path(id): Path<String>) -> Result<ResponseJson<ReplayResponse>, StatusCode> {
+    let record = load_run(&id)?;
+    info!("Replaying run {}", record.id);
+    
+    Ok(ResponseJson(replay(record).await))
+}
+
+/// Runs a recorded run's inputs again in the current environment and
+/// compares the outcomes.
+async fn replay(record: runs::RunRecord) -> ReplayResponse {
+    let replayed = replay_outcome(&record).await;
+    ReplayResponse {
+        identical: replayed.matches(&record.outcome),
+        environment_changes: runs::environment_changes(&record),
+        run_id: record.id,
+        started_at: record.started_at,
+        recorded: record.outcome,
+        replayed,
+    }
+}
+
+async fn replay_outcome(record: &runs::RunRecord) -> runs::RunOutcome {
+    match record.inputs {
+        runs::RunInputs::Run {
+            lint,
+            ref instruction_data,
+            compute_budget,
+        } => {
+            let request = RunCodeRequest {
+                code: record.code.clone(),
+                lesson: record.lesson.clone(),
+                lint,
+                instruction_data: instruction_data.clone(),
+                compute_budget: Some(compute_budget),
+            };
+            let response = execute_run(&request).await;
+            runs::RunOutcome {
+                success: response.success,
+                output: response.output,
+                error: response.error,
+                simulation: None,
+            }
+        }
+        runs::RunInputs::Simulate { ref transaction } => {
+            let result = simulate::simulate(&record.code, record.lesson.as_deref(), transaction).await;
+            let response = simulate_response(result);
+            runs::RunOutcome {
+                success: response.success,
+                output: None,
+                error: response.error,
+                simulation: response.simulation,
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct LspQuery {
+    lesson: Option<String>,
+}
+
+async fn language_server(ws: WebSocketUpgrade, Query(query): Query<LspQuery>) -> Response {
+    info!("Received language server connection");
+    
+    match lsp_bridge::LspSession::start(query.lesson.as_deref()).await {
+        Ok(session) => ws.on_upgrade(move |socket| session.bridge(socket)),
+        Err(err) => {
+            error!("Failed to start language server: {}", err);
+            (StatusCode::SERVICE_UNAVAILABLE, err).into_response()
+        }
+    }
+}
+
 async fn health_check() -> &'static str {
     "Bitcoin Zombies Backend is running!"
 }
@@ -59,6 +578,17 @@ async fn main() {
     // Build our application with routes
     let app = Router::new()
         .route("/api/run-arch", post(run_arch_code))
+        .route("/api/export", post(export_project))
+        .route("/api/format", post(format_code))
+        .route("/api/hints", post(get_hints))
+        .route("/api/simulate", post(simulate_transaction))
+        .route("/api/pda", post(derive_program_address))
+        .route("/api/runs/:id", axum::routing::get(get_run))
+        .route("/api/runs/:id/replay", axum::routing::get(replay_run))
+        .route("/api/sessions/:id/accounts", axum::routing::get(list_session_accounts))
+        .route("/api/sessions/:id/accounts/:pubkey", axum::routing::get(get_session_account))
+        .route("/api/sessions/:id/reset", post(reset_session))
+        .route("/api/lsp", axum::routing::get(language_server))
         .route("/health", axum::routing::get(health_check))
         .layer(CorsLayer::permissive());
 
@@ -68,4 +598,70 @@ async fn main() {
     
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_request(compute_budget: Option<u64>) -> RunCodeRequest {
+        RunCodeRequest {
+            code: String::new(),
+            lesson: None,
+            lint: false,
+            instruction_data: Vec::new(),
+            compute_budget,
+        }
+    }
+
+    #[test]
+    fn compute_budget_defaults_and_is_capped() {
+        assert_eq!(
+            run_request(None).run_options().compute_budget,
+            metering::DEFAULT_COMPUTE_BUDGET
+        );
+        assert_eq!(run_request(Some(5_000)).run_options().compute_budget, 5_000);
+        assert_eq!(
+            run_request(Some(u64::MAX)).run_options().compute_budget,
+            metering::MAX_COMPUTE_BUDGET
+        );
+    }
+
+    #[tokio::test]
+    async fn recorded_runs_replay_identically() {
+        // Missing the lesson's entrypoint fails before cargo runs, so this
+        // needs no network or toolchain
+        let request = RunCodeRequest {
+            code: "pub fn other() {}".to_string(),
+            lesson: Some("hello_world_program".to_string()),
+            ..run_request(Some(5_000))
+        };
+        // An old run, so a fresh timestamp can't stand in for the original
+        let started_at = runs::unix_time() - 3_600;
+        let response = execute_run(&request).await;
+        assert!(!response.success);
+        let record = runs::RunRecord::new(
+            &request.code,
+            request.lesson.as_deref(),
+            started_at,
+            runs::RunInputs::Run {
+                lint: request.lint,
+                instruction_data: request.instruction_data.clone(),
+                compute_budget: request.run_options().compute_budget,
+            },
+            runs::RunOutcome {
+                success: response.success,
+                output: response.output,
+                error: response.error,
+                simulation: None,
+            },
+        );
+
+        let replay = replay(record).await;
+
+        assert_eq!(replay.replayed.error, replay.recorded.error);
+        assert!(replay.identical);
+        assert!(replay.environment_changes.is_empty());
+        assert_eq!(replay.started_at, started_at);
+    }
+}
@@ -0,0 +1,230 @@
+use quote::{quote, ToTokens};
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    Block, Expr, ExprClosure, ExprConst, ImplItemConst, ImplItemFn, ItemConst, ItemFn, ItemStatic,
+    TraitItemConst,
+};
+
+/// Budget used when a request doesn't set one, matching the default
+/// per-instruction compute limit on Arch.
+pub const DEFAULT_COMPUTE_BUDGET: u64 = 200_000;
+
+/// Largest budget a request may ask for, matching the per-transaction
+/// compute limit; larger requests are clamped to it.
+pub const MAX_COMPUTE_BUDGET: u64 = 1_400_000;
+
+/// Panic message the meter raises once the budget is spent.
+pub const BUDGET_EXCEEDED: &str = "compute budget exceeded";
+
+/// Rewrites a submission so every executed block charges compute units:
+/// one unit per statement, charged each time the block runs, so loops and
+/// repeated calls cost proportionally more. This approximates instruction
+/// counting without an on-chain VM.
+///
+/// Only the submission's own code is metered: const fns and const blocks
+/// can't call the meter, and work done inside std (an iterator adapter's
+/// loop, say) costs nothing beyond the submission's closures. The runner's
+/// execution timeout is what bounds those.
+pub fn instrument(code: &str) -> Result<String, String> {
+    let mut file = syn::parse_file(code).map_err(|e| format!("Failed to parse code: {}", e))?;
+    Instrumenter.visit_file_mut(&mut file);
+
+    let budget_exceeded = BUDGET_EXCEEDED;
+    let meter = quote! {
+        #[doc(hidden)]
+        pub mod __zombies_meter {
+            use std::cell::Cell;
+
+            thread_local! {
+                static CONSUMED: Cell<u64> = const { Cell::new(0) };
+                static BUDGET: Cell<u64> = const { Cell::new(u64::MAX) };
+            }
+
+            pub fn reset(budget: u64) {
+                CONSUMED.with(|consumed| consumed.set(0));
+                BUDGET.with(|limit| limit.set(budget));
+            }
+
+            pub fn consumed() -> u64 {
+                CONSUMED.with(|consumed| consumed.get())
+            }
+
+            pub fn consume(units: u64) {
+                let total = CONSUMED.with(|consumed| {
+                    let total = consumed.get().saturating_add(units);
+                    consumed.set(total);
+                    total
+                });
+                if total > BUDGET.with(|limit| limit.get()) {
+                    panic!(#budget_exceeded);
+                }
+            }
+        }
+    };
+
+    Ok(format!("{}\n{}", file.into_token_stream(), meter))
+}
+
+struct Instrumenter;
+
+impl VisitMut for Instrumenter {
+    fn visit_item_fn_mut(&mut self, item: &mut ItemFn) {
+        // const fns can't call the meter
+        if item.sig.constness.is_none() {
+            visit_mut::visit_item_fn_mut(self, item);
+        }
+    }
+
+    fn visit_impl_item_fn_mut(&mut self, item: &mut ImplItemFn) {
+        if item.sig.constness.is_none() {
+            visit_mut::visit_impl_item_fn_mut(self, item);
+        }
+    }
+
+    fn visit_item_const_mut(&mut self, _item: &mut ItemConst) {}
+
+    fn visit_impl_item_const_mut(&mut self, _item: &mut ImplItemConst) {}
+
+    fn visit_trait_item_const_mut(&mut self, _item: &mut TraitItemConst) {}
+
+    fn visit_item_static_mut(&mut self, _item: &mut ItemStatic) {}
+
+    fn visit_expr_const_mut(&mut self, _expr: &mut ExprConst) {}
+
+    fn visit_expr_closure_mut(&mut self, closure: &mut ExprClosure) {
+        visit_mut::visit_expr_closure_mut(self, closure);
+        // Block bodies are charged like any other block; an expression body
+        // has no block, so give it one that charges a unit per call
+        if !matches!(*closure.body, Expr::Block(_)) {
+            let body = &closure.body;
+            *closure.body = syn::parse_quote!({
+                crate::__zombies_meter::consume(1);
+                #body
+            });
+        }
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Block) {
+        visit_mut::visit_block_mut(self, block);
+        let units = block.stmts.len().max(1) as u64;
+        block
+            .stmts
+            .insert(0, syn::parse_quote!(crate::__zombies_meter::consume(#units);));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Compiles an instrumented submission as a binary that calls its `run`
+    /// function under `budget`, returning the units consumed or the panic
+    /// message.
+    fn run_metered(code: &str, budget: u64) -> Result<u64, String> {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("main.rs");
+        let binary = dir.path().join("main");
+        let main = format!(
+            r#"
+            fn main() {{
+                std::panic::set_hook(Box::new(|_| {{}}));
+                __zombies_meter::reset({});
+                let result = std::panic::catch_unwind(run);
+                match result {{
+                    Ok(()) => print!("ok {{}}", __zombies_meter::consumed()),
+                    Err(panic) => print!("panic {{}}", panic.downcast_ref::<&str>().unwrap()),
+                }}
+            }}
+            "#,
+            budget
+        );
+        std::fs::write(&source, format!("{}\n{}", instrument(code).unwrap(), main)).unwrap();
+
+        let compile = Command::new("rustc")
+            .args(["--edition", "2021", "-A", "warnings", "-o"])
+            .arg(&binary)
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(
+            compile.status.success(),
+            "instrumented code failed to compile:\n{}",
+            String::from_utf8_lossy(&compile.stderr)
+        );
+
+        let output = Command::new(&binary).output().unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        match stdout.split_once(' ').unwrap() {
+            ("ok", units) => Ok(units.parse().unwrap()),
+            (_, message) => Err(message.to_string()),
+        }
+    }
+
+    #[test]
+    fn loops_charge_every_iteration() {
+        let code = r#"
+            fn run() {
+                let mut total = 0u64;
+                for i in 0..10 {
+                    total += i;
+                }
+                std::hint::black_box(total);
+            }
+        "#;
+        // Three statements in `run`, then one per iteration
+        assert_eq!(run_metered(code, DEFAULT_COMPUTE_BUDGET), Ok(13));
+    }
+
+    #[test]
+    fn expression_closures_charge_every_call() {
+        let code = r#"
+            fn run() {
+                let total: u64 = (0..100u64).map(|x| x * 2).sum();
+                std::hint::black_box(total);
+            }
+        "#;
+        assert_eq!(run_metered(code, DEFAULT_COMPUTE_BUDGET), Ok(102));
+    }
+
+    #[test]
+    fn const_code_is_left_unmetered() {
+        let code = r#"
+            const fn double(x: u64) -> u64 {
+                let doubled = x * 2;
+                doubled
+            }
+
+            const FOUR: u64 = { double(2) };
+
+            struct Limits;
+
+            impl Limits {
+                const MAX: u64 = { double(FOUR) };
+            }
+
+            fn run() {
+                let inline = const { double(3) };
+                std::hint::black_box(double(FOUR) + Limits::MAX + inline);
+            }
+        "#;
+        assert_eq!(run_metered(code, DEFAULT_COMPUTE_BUDGET), Ok(2));
+    }
+
+    #[test]
+    fn exceeding_the_budget_panics() {
+        let code = r#"
+            fn run() {
+                loop {
+                    std::hint::black_box(0);
+                }
+            }
+        "#;
+        assert_eq!(run_metered(code, 1_000), Err(BUDGET_EXCEEDED.to_string()));
+    }
+
+    #[test]
+    fn unparsable_code_is_an_error() {
+        assert!(instrument("fn run( {").is_err());
+    }
+}
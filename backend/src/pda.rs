@@ -0,0 +1,339 @@
+use secp256k1::XOnlyPublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Most seeds an address may be derived from, the bump included.
+pub const MAX_SEEDS: usize = 16;
+/// Longest a single seed may be.
+pub const MAX_SEED_LEN: usize = 32;
+/// Appended to every derivation so program addresses can't collide with
+/// hashes used elsewhere.
+const PDA_MARKER: &[u8] = b"ProgramDerivedAddress";
+
+/// One seed as a learner writes it; each form is turned into the bytes a
+/// program would pass to `find_program_address`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Seed {
+    /// `b"vault"` in a program.
+    Utf8(String),
+    /// Raw bytes as hex.
+    Hex(String),
+    /// `pubkey.as_ref()`, given as a 64-character hex key.
+    Pubkey(String),
+    /// `&[n]`.
+    U8(u8),
+    /// `&n.to_le_bytes()`.
+    U64(u64),
+}
+
+impl Seed {
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Seed::Utf8(text) => Ok(text.as_bytes().to_vec()),
+            Seed::Hex(hex) => decode_hex(hex),
+            Seed::Pubkey(key) => decode_pubkey(key).map(|key| key.to_vec()),
+            Seed::U8(value) => Ok(vec![*value]),
+            Seed::U64(value) => Ok(value.to_le_bytes().to_vec()),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Seed::Utf8(text) => format!("b\"{}\"", text),
+            Seed::Hex(hex) => format!("0x{}", hex),
+            Seed::Pubkey(key) => format!("pubkey {}", key),
+            Seed::U8(value) => format!("&[{}]", value),
+            Seed::U64(value) => format!("&{}u64.to_le_bytes()", value),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedExplanation {
+    pub seed: String,
+    pub hex: String,
+    pub len: usize,
+}
+
+/// One bump tried while searching for an off-curve address.
+#[derive(Debug, Serialize)]
+pub struct BumpAttempt {
+    pub bump: u8,
+    pub address: String,
+    pub on_curve: bool,
+}
+
+/// A derived address plus everything needed to explain how it was found.
+#[derive(Debug, Serialize)]
+pub struct ProgramAddress {
+    pub address: String,
+    pub bump: u8,
+    pub program_id: String,
+    pub seeds: Vec<SeedExplanation>,
+    pub attempts: Vec<BumpAttempt>,
+    pub explanation: Vec<String>,
+}
+
+/// `Pubkey::find_program_address`: tries bumps from 255 down to 1 until the
+/// hash is not a valid public key, so no private key can sign for it. Like
+/// the reference, bump 0 is never tried.
+pub fn find_program_address(seeds: &[Seed], program_id: &str) -> Result<ProgramAddress, String> {
+    let program = decode_pubkey(program_id)?;
+    let seed_bytes = seed_bytes(seeds)?;
+
+    let mut attempts = Vec::new();
+    for bump in (1..=u8::MAX).rev() {
+        let hash = hash_seeds(&seed_bytes, bump, &program);
+        let on_curve = is_on_curve(&hash);
+        attempts.push(BumpAttempt {
+            bump,
+            address: hex(&hash),
+            on_curve,
+        });
+        if !on_curve {
+            let mut explanation = base_explanation(seeds, &seed_bytes, program_id);
+            explanation.push(format!(
+                "Try bumps from 255 down, appending `&[bump]` as a final seed: {} of {} candidates were valid public keys and were skipped",
+                attempts.len() - 1,
+                attempts.len()
+            ));
+            explanation.push(format!(
+                "Bump {} gives {}, which is off the secp256k1 curve, so only the program can sign for it with `invoke_signed`",
+                bump,
+                hex(&hash)
+            ));
+            explanation.push(
+                "Store the bump on-chain and pass it back in later so the program can call `create_program_address` instead of searching again"
+                    .to_string(),
+            );
+            return Ok(ProgramAddress {
+                address: hex(&hash),
+                bump,
+                program_id: program_id.to_string(),
+                seeds: explain_seeds(seeds, &seed_bytes),
+                attempts,
+                explanation,
+            });
+        }
+    }
+
+    Err("Unable to find a viable program address bump seed".to_string())
+}
+
+/// `Pubkey::create_program_address` with a known bump: fails when the
+/// seeds and bump land on the curve.
+pub fn create_program_address(
+    seeds: &[Seed],
+    bump: u8,
+    program_id: &str,
+) -> Result<ProgramAddress, String> {
+    let program = decode_pubkey(program_id)?;
+    let seed_bytes = seed_bytes(seeds)?;
+
+    let hash = hash_seeds(&seed_bytes, bump, &program);
+    if is_on_curve(&hash) {
+        return Err(format!(
+            "Bump {} puts the address on the curve, so it isn't a valid program address; use find_program_address to get the canonical bump",
+            bump
+        ));
+    }
+
+    let mut explanation = base_explanation(seeds, &seed_bytes, program_id);
+    explanation.push(format!(
+        "Append the bump `&[{}]` as a final seed: the result {} is off the secp256k1 curve, so only the program can sign for it",
+        bump,
+        hex(&hash)
+    ));
+    Ok(ProgramAddress {
+        address: hex(&hash),
+        bump,
+        program_id: program_id.to_string(),
+        seeds: explain_seeds(seeds, &seed_bytes),
+        attempts: vec![BumpAttempt {
+            bump,
+            address: hex(&hash),
+            on_curve: false,
+        }],
+        explanation,
+    })
+}
+
+fn seed_bytes(seeds: &[Seed]) -> Result<Vec<Vec<u8>>, String> {
+    // The bump takes the last seed slot
+    if seeds.len() + 1 > MAX_SEEDS {
+        return Err(format!(
+            "At most {} seeds are allowed besides the bump; got {}",
+            MAX_SEEDS - 1,
+            seeds.len()
+        ));
+    }
+    seeds
+        .iter()
+        .map(|seed| {
+            let bytes = seed.to_bytes()?;
+            if bytes.len() > MAX_SEED_LEN {
+                return Err(format!(
+                    "Seed {} is {} bytes; seeds can be at most {} bytes",
+                    seed.describe(),
+                    bytes.len(),
+                    MAX_SEED_LEN
+                ));
+            }
+            Ok(bytes)
+        })
+        .collect()
+}
+
+/// sha256(seeds ‖ bump ‖ program id ‖ "ProgramDerivedAddress")
+fn hash_seeds(seeds: &[Vec<u8>], bump: u8, program_id: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for seed in seeds {
+        hasher.update(seed);
+    }
+    hasher.update([bump]);
+    hasher.update(program_id);
+    hasher.update(PDA_MARKER);
+    hasher.finalize().into()
+}
+
+/// Arch keys are x-only secp256k1 keys, so an address is "on the curve"
+/// when its bytes are the x coordinate of a curve point.
+fn is_on_curve(bytes: &[u8; 32]) -> bool {
+    XOnlyPublicKey::from_slice(bytes).is_ok()
+}
+
+fn base_explanation(seeds: &[Seed], seed_bytes: &[Vec<u8>], program_id: &str) -> Vec<String> {
+    let mut explanation = vec![format!(
+        "Hash the seeds, then the program id {}, then the marker \"ProgramDerivedAddress\" with sha256",
+        program_id
+    )];
+    for (seed, bytes) in seeds.iter().zip(seed_bytes) {
+        explanation.push(format!(
+            "Seed {} is {} bytes: {}",
+            seed.describe(),
+            bytes.len(),
+            hex(bytes)
+        ));
+    }
+    explanation
+}
+
+fn explain_seeds(seeds: &[Seed], seed_bytes: &[Vec<u8>]) -> Vec<SeedExplanation> {
+    seeds
+        .iter()
+        .zip(seed_bytes)
+        .map(|(seed, bytes)| SeedExplanation {
+            seed: seed.describe(),
+            hex: hex(bytes),
+            len: bytes.len(),
+        })
+        .collect()
+}
+
+fn decode_pubkey(key: &str) -> Result<[u8; 32], String> {
+    decode_hex(key)?
+        .try_into()
+        .map_err(|_| format!("Invalid pubkey {}: expected 32 bytes of hex", key))
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|byte| byte.len() == 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| format!("Invalid hex {}", hex))
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Derivations shared with the local validator's tests, which check the
+    /// same vectors against `arch_program`'s `Pubkey::find_program_address`,
+    /// so the two implementations can't drift apart unnoticed.
+    const VECTORS: &str = include_str!("../testdata/pda_vectors.json");
+
+    #[derive(Deserialize)]
+    struct Vector {
+        program_id: String,
+        seeds: Vec<String>,
+        address: String,
+        bump: u8,
+    }
+
+    fn vectors() -> Vec<Vector> {
+        serde_json::from_str(VECTORS).unwrap()
+    }
+
+    fn hex_seeds(seeds: &[String]) -> Vec<Seed> {
+        seeds.iter().cloned().map(Seed::Hex).collect()
+    }
+
+    #[test]
+    fn matches_reference_vectors() {
+        for vector in vectors() {
+            let seeds = hex_seeds(&vector.seeds);
+            let found = find_program_address(&seeds, &vector.program_id).unwrap();
+            assert_eq!((found.address.as_str(), found.bump), (vector.address.as_str(), vector.bump));
+
+            let created = create_program_address(&seeds, vector.bump, &vector.program_id).unwrap();
+            assert_eq!(created.address, vector.address);
+        }
+    }
+
+    #[test]
+    fn search_skips_on_curve_bumps() {
+        for vector in vectors() {
+            let found = find_program_address(&hex_seeds(&vector.seeds), &vector.program_id).unwrap();
+            let bumps: Vec<u8> = found.attempts.iter().map(|attempt| attempt.bump).collect();
+            let expected: Vec<u8> = (vector.bump..=u8::MAX).rev().collect();
+            assert_eq!(bumps, expected);
+
+            let (last, skipped) = found.attempts.split_last().unwrap();
+            assert!(!last.on_curve);
+            assert!(skipped.iter().all(|attempt| attempt.on_curve));
+            for attempt in skipped {
+                assert!(create_program_address(&hex_seeds(&vector.seeds), attempt.bump, &vector.program_id).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn seed_forms_encode_like_a_program() {
+        let seeds = [
+            Seed::Utf8("vault".to_string()),
+            Seed::U8(7),
+            Seed::U64(42),
+            Seed::Pubkey("ab".repeat(32)),
+        ];
+        let bytes = seed_bytes(&seeds).unwrap();
+        assert_eq!(bytes[0], b"vault");
+        assert_eq!(bytes[1], [7]);
+        assert_eq!(bytes[2], 42u64.to_le_bytes());
+        assert_eq!(bytes[3], [0xab; 32]);
+    }
+
+    #[test]
+    fn invalid_seeds_are_rejected() {
+        let program_id = "00".repeat(32);
+        let too_many: Vec<Seed> = (0..MAX_SEEDS as u8).map(Seed::U8).collect();
+        assert!(find_program_address(&too_many, &program_id).is_err());
+
+        let too_long = [Seed::Hex("00".repeat(MAX_SEED_LEN + 1))];
+        assert!(find_program_address(&too_long, &program_id).is_err());
+
+        assert!(find_program_address(&[Seed::Hex("zz".to_string())], &program_id).is_err());
+        assert!(find_program_address(&[Seed::Pubkey("abcd".to_string())], &program_id).is_err());
+        assert!(find_program_address(&[], "not hex").is_err());
+    }
+}
@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{ExprMatch, FnArg, Item, ItemFn, Pat, ReturnType, Type};
+
+/// Structural requirements a lesson declares in its Cargo.toml under
+/// `[package.metadata.zombies]`.
+#[derive(Debug, Default, Deserialize)]
+pub struct Requirements {
+    /// Function that must be registered with `entrypoint!` and have the
+    /// `(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult` signature.
+    pub entrypoint: Option<String>,
+    /// Enum whose variants must all be handled by a `match`.
+    pub instruction_enum: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Violation {
+    pub message: String,
+    pub line: Option<usize>,
+}
+
+impl Violation {
+    fn new(message: String, line: Option<usize>) -> Self {
+        Violation { message, line }
+    }
+}
+
+/// Checks a submission against lesson requirements without compiling it.
+pub fn check_requirements(code: &str, requirements: &Requirements) -> Vec<Violation> {
+    let file = match syn::parse_file(code) {
+        Ok(file) => file,
+        Err(e) => {
+            return vec![Violation::new(
+                format!("Code does not parse: {}", e),
+                Some(e.span().start().line),
+            )]
+        }
+    };
+
+    let mut violations = Vec::new();
+
+    if let Some(entrypoint) = &requirements.entrypoint {
+        check_entrypoint(&file, entrypoint, &mut violations);
+    }
+    if let Some(instruction_enum) = &requirements.instruction_enum {
+        check_instruction_match(&file, instruction_enum, &mut violations);
+    }
+
+    violations
+}
+
+fn check_entrypoint(file: &syn::File, entrypoint: &str, violations: &mut Vec<Violation>) {
+    let registered = file.items.iter().any(|item| match item {
+        // Either `entrypoint!(..)` or a path to it, like `arch_program::entrypoint!(..)`
+        Item::Macro(item) => {
+            item.mac
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "entrypoint")
+                && item.mac.tokens.to_string() == entrypoint
+        }
+        _ => false,
+    });
+    if !registered {
+        violations.push(Violation::new(
+            format!("Missing `entrypoint!({});`", entrypoint),
+            None,
+        ));
+    }
+
+    let function = file.items.iter().find_map(|item| match item {
+        Item::Fn(function) if function.sig.ident == entrypoint => Some(function),
+        _ => None,
+    });
+    match function {
+        Some(function) => check_entrypoint_signature(function, violations),
+        None => violations.push(Violation::new(
+            format!("Missing the `fn {}` entrypoint function", entrypoint),
+            None,
+        )),
+    }
+}
+
+fn check_entrypoint_signature(function: &ItemFn, violations: &mut Vec<Violation>) {
+    let name = &function.sig.ident;
+    let line = Some(name.span().start().line);
+    let expected = ["&Pubkey", "&[AccountInfo]", "&[u8]"];
+
+    let params: Vec<_> = function
+        .sig
+        .inputs
+        .iter()
+        .map(|input| match input {
+            FnArg::Typed(param) => type_shape(&param.ty),
+            FnArg::Receiver(_) => "self".to_string(),
+        })
+        .collect();
+
+    if params.len() != expected.len() {
+        violations.push(Violation::new(
+            format!(
+                "`{}` should take {} parameters ({}), found {}",
+                name,
+                expected.len(),
+                expected.join(", "),
+                params.len()
+            ),
+            line,
+        ));
+    } else {
+        for (index, (found, expected)) in params.iter().zip(expected).enumerate() {
+            if found != expected {
+                violations.push(Violation::new(
+                    format!(
+                        "Parameter {} of `{}` should be `{}`, found `{}`",
+                        index + 1,
+                        name,
+                        expected,
+                        found
+                    ),
+                    line,
+                ));
+            }
+        }
+    }
+
+    let returns_program_result = match &function.sig.output {
+        ReturnType::Type(_, ty) => type_shape(ty) == "ProgramResult",
+        ReturnType::Default => false,
+    };
+    if !returns_program_result {
+        violations.push(Violation::new(
+            format!("`{}` should return `ProgramResult`", name),
+            line,
+        ));
+    }
+}
+
+/// Reduces a type to the shape learners write, ignoring module paths,
+/// lifetimes and generics: `&'a [arch_program::account::AccountInfo<'a>]`
+/// becomes `&[AccountInfo]`.
+fn type_shape(ty: &Type) -> String {
+    match ty {
+        Type::Reference(reference) => format!(
+            "&{}{}",
+            if reference.mutability.is_some() { "mut " } else { "" },
+            type_shape(&reference.elem)
+        ),
+        Type::Slice(slice) => format!("[{}]", type_shape(&slice.elem)),
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string())
+            .unwrap_or_default(),
+        Type::Paren(paren) => type_shape(&paren.elem),
+        _ => "_".to_string(),
+    }
+}
+
+fn check_instruction_match(file: &syn::File, enum_name: &str, violations: &mut Vec<Violation>) {
+    let Some(variants) = file.items.iter().find_map(|item| match item {
+        Item::Enum(item) if item.ident == enum_name => Some(
+            item.variants
+                .iter()
+                .map(|variant| variant.ident.to_string())
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    }) else {
+        violations.push(Violation::new(
+            format!("Missing the `{}` enum", enum_name),
+            None,
+        ));
+        return;
+    };
+
+    let mut finder = MatchFinder {
+        enum_name,
+        best: None,
+    };
+    finder.visit_file(file);
+
+    let Some((line, handled)) = finder.best else {
+        violations.push(Violation::new(
+            format!("No `match` over `{}` found", enum_name),
+            None,
+        ));
+        return;
+    };
+    let missing: Vec<_> = variants
+        .iter()
+        .filter(|variant| !handled.iter().any(|h| h == "*" || h == *variant))
+        .map(|variant| format!("`{}::{}`", enum_name, variant))
+        .collect();
+    if !missing.is_empty() {
+        violations.push(Violation::new(
+            format!(
+                "The `match` on `{}` doesn't handle {}",
+                enum_name,
+                missing.join(", ")
+            ),
+            Some(line),
+        ));
+    }
+}
+
+/// Finds the `match` with the most arms naming variants of the instruction
+/// enum; a wildcard arm counts as handling everything.
+struct MatchFinder<'a> {
+    enum_name: &'a str,
+    best: Option<(usize, Vec<String>)>,
+}
+
+impl<'ast> Visit<'ast> for MatchFinder<'_> {
+    fn visit_expr_match(&mut self, expr: &'ast ExprMatch) {
+        let mut handled = Vec::new();
+        let mut wildcard = false;
+        for arm in &expr.arms {
+            collect_variants(&arm.pat, self.enum_name, &mut handled, &mut wildcard);
+        }
+        if wildcard && !handled.is_empty() {
+            handled.push("*".to_string());
+        }
+
+        if !handled.is_empty() {
+            let covers_more = match &self.best {
+                Some((_, best)) => handled.len() > best.len(),
+                None => true,
+            };
+            if covers_more {
+                self.best = Some((expr.match_token.span().start().line, handled));
+            }
+        }
+        visit::visit_expr_match(self, expr);
+    }
+}
+
+fn collect_variants(pat: &Pat, enum_name: &str, handled: &mut Vec<String>, wildcard: &mut bool) {
+    let path = match pat {
+        Pat::Path(pat) => Some(&pat.path),
+        Pat::Struct(pat) => Some(&pat.path),
+        Pat::TupleStruct(pat) => Some(&pat.path),
+        Pat::Or(pat) => {
+            for case in &pat.cases {
+                collect_variants(case, enum_name, handled, wildcard);
+            }
+            None
+        }
+        Pat::Wild(_) | Pat::Ident(_) => {
+            *wildcard = true;
+            None
+        }
+        _ => None,
+    };
+
+    if let Some(path) = path {
+        let segments: Vec<_> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+        if let [.., owner, variant] = segments.as_slice() {
+            if owner == enum_name {
+                handled.push(variant.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lessons;
+
+    const PROGRAM: &str = r#"
+use arch_program::{account::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey};
+
+entrypoint!(process_instruction);
+
+pub enum TokenInstruction {
+    Mint { amount: u64 },
+    Burn { amount: u64 },
+    Freeze,
+}
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    match TokenInstruction::unpack(instruction_data)? {
+        TokenInstruction::Mint { amount } => process_mint(accounts, amount),
+        TokenInstruction::Burn { amount } => process_burn(accounts, amount),
+        TokenInstruction::Freeze => process_freeze(accounts),
+    }
+}
+"#;
+
+    fn requirements() -> Requirements {
+        Requirements {
+            entrypoint: Some("process_instruction".to_string()),
+            instruction_enum: Some("TokenInstruction".to_string()),
+        }
+    }
+
+    fn messages(code: &str) -> Vec<String> {
+        check_requirements(code, &requirements())
+            .into_iter()
+            .map(|violation| violation.message)
+            .collect()
+    }
+
+    #[test]
+    fn complete_programs_pass() {
+        assert!(messages(PROGRAM).is_empty());
+        assert!(check_requirements("", &Requirements::default()).is_empty());
+    }
+
+    #[test]
+    fn lesson_references_meet_their_own_requirements() {
+        for name in ["hello_world_program", "token_operations"] {
+            let lesson = lessons::load_lesson(name).unwrap();
+            let violations = check_requirements(&lesson.reference_code, &lesson.requirements);
+            assert!(violations.is_empty(), "{}: {:?}", name, violations);
+        }
+    }
+
+    #[test]
+    fn entrypoint_may_be_registered_by_path() {
+        let code = PROGRAM.replace("\nentrypoint!", "\narch_program::entrypoint!");
+        assert!(messages(&code).is_empty());
+    }
+
+    #[test]
+    fn missing_entrypoint_is_reported() {
+        let unregistered = PROGRAM.replace("entrypoint!(process_instruction);", "");
+        assert_eq!(messages(&unregistered), ["Missing `entrypoint!(process_instruction);`"]);
+
+        let wrong_function = PROGRAM.replace("entrypoint!(process_instruction)", "entrypoint!(process)");
+        assert_eq!(messages(&wrong_function), ["Missing `entrypoint!(process_instruction);`"]);
+
+        let undefined = PROGRAM.replace("pub fn process_instruction", "pub fn process");
+        assert_eq!(messages(&undefined), ["Missing the `fn process_instruction` entrypoint function"]);
+    }
+
+    #[test]
+    fn entrypoint_signature_is_checked() {
+        let code = PROGRAM
+            .replace("instruction_data: &[u8],", "instruction_data: Vec<u8>,")
+            .replace(") -> ProgramResult {\n    match", ") {\n    match");
+        let violations = check_requirements(&code, &requirements());
+
+        let found: Vec<_> = violations.iter().map(|violation| violation.message.as_str()).collect();
+        assert_eq!(
+            found,
+            [
+                "Parameter 3 of `process_instruction` should be `&[u8]`, found `Vec`",
+                "`process_instruction` should return `ProgramResult`",
+            ]
+        );
+        assert_eq!(violations[0].line, Some(12));
+
+        let missing_param = PROGRAM.replace("    instruction_data: &[u8],\n", "");
+        assert_eq!(
+            messages(&missing_param),
+            ["`process_instruction` should take 3 parameters (&Pubkey, &[AccountInfo], &[u8]), found 2"]
+        );
+    }
+
+    #[test]
+    fn types_are_compared_by_shape() {
+        let code = PROGRAM
+            .replace("program_id: &Pubkey", "program_id: &arch_program::pubkey::Pubkey")
+            .replace("accounts: &[AccountInfo]", "accounts: &'a [AccountInfo<'a>]")
+            .replace("pub fn process_instruction(", "pub fn process_instruction<'a>(");
+        assert!(messages(&code).is_empty());
+    }
+
+    #[test]
+    fn unhandled_variants_are_reported_at_the_match() {
+        let code = PROGRAM.replace("        TokenInstruction::Freeze => process_freeze(accounts),\n", "");
+        let violations = check_requirements(&code, &requirements());
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].message, "The `match` on `TokenInstruction` doesn't handle `TokenInstruction::Freeze`");
+        assert_eq!(violations[0].line, Some(17));
+    }
+
+    #[test]
+    fn wildcards_and_or_patterns_cover_variants() {
+        let wildcard = PROGRAM.replace("TokenInstruction::Freeze =>", "_ =>");
+        assert!(messages(&wildcard).is_empty());
+
+        let or_pattern = PROGRAM.replace(
+            "TokenInstruction::Burn { amount } => process_burn(accounts, amount),\n        TokenInstruction::Freeze",
+            "TokenInstruction::Burn { .. } | TokenInstruction::Freeze",
+        );
+        assert!(messages(&or_pattern).is_empty());
+    }
+
+    #[test]
+    fn missing_enum_or_match_is_reported() {
+        let no_enum = PROGRAM.replace("pub enum TokenInstruction", "pub enum Instruction");
+        assert_eq!(messages(&no_enum), ["Missing the `TokenInstruction` enum"]);
+
+        let no_match = PROGRAM.replace("TokenInstruction::", "Other::");
+        assert_eq!(messages(&no_match), ["No `match` over `TokenInstruction` found"]);
+    }
+
+    #[test]
+    fn unparsable_code_is_reported_with_its_line() {
+        let violations = check_requirements("fn ok() {}\nfn broken(", &requirements());
+
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].message.starts_with("Code does not parse"));
+        assert_eq!(violations[0].line, Some(2));
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::arch_runner::toolchain_command;
+use crate::lessons;
+use crate::simulate::SimulationSpec;
+
+/// Everything a run was given and what it produced, so it can be replayed
+/// later with the same inputs and the results compared.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RunRecord {
+    pub id: String,
+    pub lesson: Option<String>,
+    /// Hash of the lesson's Cargo.toml and tests at the time of the run.
+    pub lesson_hash: Option<String>,
+    pub code: String,
+    pub code_hash: String,
+    /// `rustc --version` of the runner toolchain.
+    pub toolchain: String,
+    /// Unix time the run started. Kept out of the replay comparison, which
+    /// only looks at outcomes.
+    pub started_at: u64,
+    pub inputs: RunInputs,
+    pub outcome: RunOutcome,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunInputs {
+    /// `/api/run-arch`
+    Run {
+        lint: bool,
+        instruction_data: Vec<u8>,
+        compute_budget: u64,
+    },
+    /// `/api/simulate`, with any session accounts already merged in so the
+    /// replay doesn't depend on the ledger's current state.
+    Simulate { transaction: SimulationSpec },
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RunOutcome {
+    pub success: bool,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    pub simulation: Option<serde_json::Value>,
+}
+
+impl RunOutcome {
+    /// Compares outcomes ignoring the random temp workspace paths that show
+    /// up in compiler output.
+    pub fn matches(&self, other: &RunOutcome) -> bool {
+        let mask = |outcome: &RunOutcome| {
+            serde_json::to_string(outcome).map(|json| mask_workspace_paths(&json))
+        };
+        match (mask(self), mask(other)) {
+            (Ok(this), Ok(other)) => this == other,
+            _ => self == other,
+        }
+    }
+}
+
+/// Replaces `<temp dir>/.tmpXXXXXX` workspace paths with `<workspace>`.
+fn mask_workspace_paths(text: &str) -> String {
+    let prefix = env::temp_dir().join(".tmp").to_string_lossy().into_owned();
+    let mut masked = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(&prefix) {
+        masked.push_str(&rest[..start]);
+        masked.push_str("<workspace>");
+        rest = rest[start + prefix.len()..].trim_start_matches(|c: char| c.is_ascii_alphanumeric());
+    }
+    masked.push_str(rest);
+    masked
+}
+
+pub fn runs_dir() -> PathBuf {
+    env::var("ARCH_RUNS_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir().join("bitcoin-zombies-runs"))
+}
+
+fn run_path(id: &str) -> Result<PathBuf, String> {
+    // Run ids are uuids, but they come back in from request paths
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+        return Err(format!("Invalid run id: {}", id));
+    }
+    Ok(runs_dir().join(format!("{}.json", id)))
+}
+
+impl RunRecord {
+    pub fn new(
+        code: &str,
+        lesson: Option<&str>,
+        started_at: u64,
+        inputs: RunInputs,
+        outcome: RunOutcome,
+    ) -> Self {
+        RunRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            lesson: lesson.map(str::to_string),
+            lesson_hash: lesson.and_then(lesson_hash),
+            code: code.to_string(),
+            code_hash: format!("{:x}", Sha256::digest(code.as_bytes())),
+            toolchain: toolchain_version(),
+            started_at,
+            inputs,
+            outcome,
+        }
+    }
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+pub fn save_run(record: &RunRecord) -> Result<(), String> {
+    let path = run_path(&record.id)?;
+    fs::create_dir_all(runs_dir()).map_err(|e| format!("Failed to create runs directory: {}", e))?;
+    let contents = serde_json::to_string_pretty(record)
+        .map_err(|e| format!("Failed to serialize run: {}", e))?;
+    fs::write(path, contents).map_err(|e| format!("Failed to write run {}: {}", record.id, e))
+}
+
+/// Loads a recorded run, or `None` if no run has that id.
+pub fn load_run(id: &str) -> Result<Option<RunRecord>, String> {
+    let path = run_path(id)?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse run {}: {}", id, e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read run {}: {}", id, e)),
+    }
+}
+
+/// `rustc --version`, asked once per process: the toolchain doesn't change
+/// under a running server, and every run records it.
+pub fn toolchain_version() -> String {
+    static VERSION: OnceLock<String> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            toolchain_command("rustc", &env::temp_dir())
+                .arg("--version")
+                .output()
+                .ok()
+                .filter(|output| output.status.success())
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                .unwrap_or_else(|| "unknown".to_string())
+        })
+        .clone()
+}
+
+pub fn lesson_hash(name: &str) -> Option<String> {
+    let lesson = lessons::load_lesson(name).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(&lesson.cargo_toml);
+    for (file_name, contents) in &lesson.tests {
+        hasher.update(file_name);
+        hasher.update(contents);
+    }
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// What's different about the environment a replay runs in, e.g. an
+/// upgraded toolchain or edited lesson tests, to explain changed results.
+pub fn environment_changes(record: &RunRecord) -> Vec<String> {
+    let mut changes = Vec::new();
+    let toolchain = toolchain_version();
+    if toolchain != record.toolchain {
+        changes.push(format!(
+            "Toolchain changed from {} to {}",
+            record.toolchain, toolchain
+        ));
+    }
+    if let Some(lesson) = &record.lesson {
+        if lesson_hash(lesson) != record.lesson_hash {
+            changes.push(format!("Lesson {} changed since the run", lesson));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn failed_compile(workspace: &TempDir) -> RunOutcome {
+        let lib = workspace.path().join("src").join("lib.rs");
+        RunOutcome {
+            success: false,
+            output: None,
+            error: Some(format!(
+                "Compilation failed:\nerror[E0425]: cannot find value `x`\n --> {}:3:5",
+                lib.display()
+            )),
+            simulation: None,
+        }
+    }
+
+    #[test]
+    fn outcomes_match_across_workspaces() {
+        // Workspaces are created the same way the runner creates them
+        let (first, second) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let recorded = failed_compile(&first);
+        let replayed = failed_compile(&second);
+
+        assert_ne!(recorded, replayed);
+        assert!(recorded.matches(&replayed));
+        assert_eq!(
+            mask_workspace_paths(recorded.error.as_deref().unwrap()),
+            "Compilation failed:\nerror[E0425]: cannot find value `x`\n --> <workspace>/src/lib.rs:3:5"
+        );
+    }
+
+    #[test]
+    fn outcomes_differing_elsewhere_do_not_match() {
+        let workspace = TempDir::new().unwrap();
+        let recorded = failed_compile(&workspace);
+
+        let mut other_error = failed_compile(&workspace);
+        other_error.error = Some("Compilation failed:\nerror[E0308]: mismatched types".to_string());
+        assert!(!recorded.matches(&other_error));
+
+        let mut succeeded = failed_compile(&workspace);
+        succeeded.success = true;
+        assert!(!recorded.matches(&succeeded));
+    }
+
+    #[test]
+    fn text_without_workspaces_is_unchanged() {
+        assert_eq!(mask_workspace_paths("Program log: hi"), "Program log: hi");
+    }
+
+    #[test]
+    fn runs_round_trip_through_disk() {
+        let record = RunRecord::new(
+            "fn main() {}",
+            None,
+            unix_time(),
+            RunInputs::Run {
+                lint: false,
+                instruction_data: vec![1, 2],
+                compute_budget: 10,
+            },
+            failed_compile(&TempDir::new().unwrap()),
+        );
+
+        save_run(&record).unwrap();
+        let loaded = load_run(&record.id).unwrap().unwrap();
+        fs::remove_file(run_path(&record.id).unwrap()).unwrap();
+
+        assert_eq!(loaded.code_hash, record.code_hash);
+        assert_eq!(loaded.toolchain, toolchain_version());
+        assert_eq!(loaded.started_at, record.started_at);
+        assert!(loaded.outcome.matches(&record.outcome));
+        assert!(environment_changes(&loaded).is_empty());
+        assert!(load_run(&record.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn run_ids_must_be_path_safe() {
+        assert!(run_path(&uuid::Uuid::new_v4().to_string()).is_ok());
+        for id in ["", "../runs", "abc.json"] {
+            assert!(run_path(id).is_err(), "{}", id);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+use quote::quote;
+
+use crate::arch_runner::{
+    check_lesson_requirements, entrypoint_name, prepare_workspace, resolve_lesson, run_harness,
+};
+use crate::ledger::LedgerAccount;
+
+const REPORT_MARKER: &str = "__ZOMBIES_SIMULATION__";
+const SPEC_FILE: &str = "simulation.json";
+
+/// A transaction to simulate, in the shape `local_validator::simulate`
+/// expects. Keys are hex pubkeys or names: `program` is the submission,
+/// `system` the system program, and any other name a throwaway keypair.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SimulationSpec {
+    pub instructions: Vec<InstructionSpec>,
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Accounts that already exist, e.g. from the session's ledger.
+    #[serde(default)]
+    pub accounts: Vec<LedgerAccount>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InstructionSpec {
+    pub program_id: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountMetaSpec>,
+    #[serde(default)]
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AccountMetaSpec {
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+pub fn local_validator_dir() -> PathBuf {
+    env::var("ARCH_LOCAL_VALIDATOR_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from("../local-validator"))
+}
+
+/// Compiles a submission with the local validator and runs the transaction
+/// against it, returning the validator's report (logs, per-instruction
+/// results and account diffs) as JSON.
+pub async fn simulate(
+    code: &str,
+    lesson: Option<&str>,
+    spec: &SimulationSpec,
+) -> Result<serde_json::Value, String> {
+    let lesson = resolve_lesson(lesson)?;
+    if let Some(lesson) = &lesson {
+        check_lesson_requirements(code, lesson)?;
+    }
+
+    let temp_dir = prepare_workspace(code, lesson.as_ref())?;
+    let temp_path = temp_dir.path();
+
+    add_validator_dependency(temp_path)?;
+    let spec_json = serde_json::to_string(spec)
+        .map_err(|e| format!("Failed to serialize simulation spec: {}", e))?;
+    fs::write(temp_path.join(SPEC_FILE), spec_json)
+        .map_err(|e| format!("Failed to write {}: {}", SPEC_FILE, e))?;
+
+    let harness = simulation_harness(entrypoint_name(lesson.as_ref()))?;
+    fs::write(
+        temp_path.join("src").join("lib.rs"),
+        format!("{}\n{}", code, harness),
+    )
+    .map_err(|e| format!("Failed to write lib.rs: {}", e))?;
+
+    info!("Simulating transaction");
+
+    let output = run_harness(temp_path, "__zombies_simulation").await?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(report) = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix(REPORT_MARKER))
+    else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Simulation failed:\n{}", stderr));
+    };
+
+    serde_json::from_str(report.trim()).map_err(|e| format!("Failed to parse simulation report: {}", e))
+}
+
+/// Adds the local validator as a dev-dependency of the workspace so the
+/// harness can execute the submission in-process.
+fn add_validator_dependency(workspace: &Path) -> Result<(), String> {
+    let cargo_toml_path = workspace.join("Cargo.toml");
+    let mut manifest: toml::Table = fs::read_to_string(&cargo_toml_path)
+        .map_err(|e| format!("Failed to read Cargo.toml: {}", e))?
+        .parse()
+        .map_err(|e| format!("Failed to parse Cargo.toml: {}", e))?;
+
+    let validator_dir = local_validator_dir();
+    let validator_dir = fs::canonicalize(&validator_dir)
+        .map_err(|e| format!("Local validator not found at {}: {}", validator_dir.display(), e))?;
+
+    let mut dependency = toml::Table::new();
+    dependency.insert(
+        "path".to_string(),
+        toml::Value::String(validator_dir.to_string_lossy().into_owned()),
+    );
+    dependency.insert(
+        "features".to_string(),
+        toml::Value::Array(vec![toml::Value::String("simulate".to_string())]),
+    );
+
+    manifest
+        .entry("dev-dependencies")
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+        .as_table_mut()
+        .ok_or("Cargo.toml has an invalid [dev-dependencies] table")?
+        .insert("local_validator".to_string(), toml::Value::Table(dependency));
+
+    let cargo_toml = toml::to_string(&manifest)
+        .map_err(|e| format!("Failed to write Cargo.toml: {}", e))?;
+    fs::write(&cargo_toml_path, cargo_toml).map_err(|e| format!("Failed to write Cargo.toml: {}", e))
+}
+
+/// Test module appended to the submission that hands its entrypoint and the
+/// spec to the local validator and prints the report.
+fn simulation_harness(entrypoint: &str) -> Result<String, String> {
+    let entrypoint: syn::Ident = syn::parse_str(entrypoint)
+        .map_err(|e| format!("Invalid entrypoint name {}: {}", entrypoint, e))?;
+    let spec_path = format!("/{}", SPEC_FILE);
+
+    Ok(quote! {
+        #[cfg(test)]
+        mod __zombies_simulation {
+            #[test]
+            fn simulate() {
+                let spec = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), #spec_path));
+                let report = local_validator::simulate::run(spec, super::#entrypoint);
+                println!("{} {}", #REPORT_MARKER, report);
+            }
+        }
+    }
+    .to_string())
+}
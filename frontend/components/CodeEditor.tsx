@@ -1,4 +1,6 @@
-import { Editor } from "@monaco-editor/react";
+import { useEffect, useRef } from "react";
+import { Editor, OnMount } from "@monaco-editor/react";
+import { connectRustAnalyzer } from "../utils/rustAnalyzerClient";
 
 interface CodeEditorProps {
   value: string;
@@ -6,6 +8,8 @@ interface CodeEditorProps {
   language?: string;
   height?: string;
   readonly?: boolean;
+  // Lesson crate whose dependencies back completions and hover
+  lesson?: string;
 }
 
 export default function CodeEditor({
@@ -14,11 +18,22 @@ export default function CodeEditor({
   language = "rust",
   height = "300px",
   readonly = false,
+  lesson,
 }: CodeEditorProps) {
+  const disconnectRef = useRef<(() => void) | null>(null);
+
   const handleEditorChange = (value: string | undefined) => {
     onChange(value || "");
   };
 
+  const handleEditorMount: OnMount = (editor, monaco) => {
+    if (lesson && language === "rust") {
+      disconnectRef.current = connectRustAnalyzer(monaco, editor, lesson);
+    }
+  };
+
+  useEffect(() => () => disconnectRef.current?.(), []);
+
   return (
     <div className="code-editor">
       <Editor
@@ -26,6 +41,7 @@ export default function CodeEditor({
         language={language}
         value={value}
         onChange={handleEditorChange}
+        onMount={handleEditorMount}
         theme="vs-dark"
         options={{
           minimap: { enabled: false },
@@ -3,7 +3,14 @@ import CodeEditor from "../../../components/CodeEditor";
 import ConsoleOutput from "../../../components/ConsoleOutput";
 import LessonSteps from "../../../components/LessonSteps";
 import Link from "next/link";
-import { ArrowLeft, Play, RotateCcw, Code2 } from "lucide-react";
+import {
+  ArrowLeft,
+  Play,
+  RotateCcw,
+  Code2,
+  Download,
+  AlignLeft,
+} from "lucide-react";
 
 const initialCode = `use arch_program::{
     account::AccountInfo,
@@ -24,6 +31,8 @@ fn process_instruction(
     Ok(())
 }`;
 
+const lessonName = "hello_world_program";
+
 export default function ArchLesson1() {
   const [code, setCode] = useState(initialCode);
   const [output, setOutput] = useState("");
@@ -40,7 +49,7 @@ export default function ArchLesson1() {
         headers: {
           "Content-Type": "application/json",
         },
-        body: JSON.stringify({ code }),
+        body: JSON.stringify({ code, lesson: lessonName }),
       });
 
       const result = await response.json();
@@ -52,6 +61,58 @@ export default function ArchLesson1() {
     }
   };
 
+  const formatCode = async () => {
+    try {
+      const response = await fetch("/api/format", {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ code, lesson: lessonName }),
+      });
+
+      const result = await response.json();
+      if (result.success) {
+        setCode(result.formatted);
+      } else if (result.error?.line) {
+        setOutput(
+          `Format failed at line ${result.error.line}, column ${result.error.column}: ${result.error.message}`
+        );
+      } else {
+        setOutput(result.error?.message || "Format failed");
+      }
+    } catch (error) {
+      setOutput(`Error: ${error}`);
+    }
+  };
+
+  const exportProject = async () => {
+    try {
+      const response = await fetch("/api/export", {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ code, lesson: lessonName }),
+      });
+
+      if (!response.ok) {
+        const result = await response.json();
+        setOutput(result.error || "Export failed");
+        return;
+      }
+
+      const url = URL.createObjectURL(await response.blob());
+      const link = document.createElement("a");
+      link.href = url;
+      link.download = `${lessonName}.zip`;
+      link.click();
+      URL.revokeObjectURL(url);
+    } catch (error) {
+      setOutput(`Error: ${error}`);
+    }
+  };
+
   const resetCode = () => {
     setCode(initialCode);
     setOutput("");
@@ -114,6 +175,20 @@ export default function ArchLesson1() {
               <RotateCcw className="h-4 w-4" />
               Reset
             </button>
+            <button
+              onClick={formatCode}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded-lg transition-colors"
+            >
+              <AlignLeft className="h-4 w-4" />
+              Format
+            </button>
+            <button
+              onClick={exportProject}
+              className="flex items-center gap-2 px-4 py-2 bg-gray-700 hover:bg-gray-600 text-white rounded-lg transition-colors"
+            >
+              <Download className="h-4 w-4" />
+              Export
+            </button>
             <button
               onClick={runCode}
               disabled={isRunning}
@@ -159,6 +234,7 @@ export default function ArchLesson1() {
                 onChange={setCode}
                 language="rust"
                 height="400px"
+                lesson={lessonName}
               />
             </div>
 
@@ -0,0 +1,239 @@
+// Minimal JSON-RPC client for the backend's rust-analyzer bridge (/api/lsp).
+// It only speaks the handful of LSP messages the editor needs: document sync,
+// completions and hover.
+
+import type { Monaco, OnMount } from "@monaco-editor/react";
+
+type EditorInstance = Parameters<OnMount>[0];
+type Position = { lineNumber: number; column: number };
+
+// The bridge maps this root onto the per-session workspace on the server
+const DOCUMENT_URI = "file:///workspace/src/lib.rs";
+
+function languageServerUrl(lesson: string): string {
+  const base =
+    process.env.NEXT_PUBLIC_BACKEND_WS_URL ||
+    `ws://${window.location.hostname}:8080`;
+  return `${base}/api/lsp?lesson=${encodeURIComponent(lesson)}`;
+}
+
+export class RustAnalyzerClient {
+  private socket: WebSocket;
+  private nextId = 1;
+  private version = 1;
+  private pending = new Map<
+    number,
+    { resolve: (result: any) => void; reject: (error: Error) => void }
+  >();
+  // Set once the socket is gone; no response will ever arrive after that
+  private failure: Error | null = null;
+  private ready: Promise<void>;
+
+  constructor(lesson: string, initialText: string) {
+    this.socket = new WebSocket(languageServerUrl(lesson));
+    this.socket.onmessage = (event) => this.handleMessage(event.data);
+    this.ready = new Promise((resolve, reject) => {
+      this.socket.onopen = () => {
+        this.initialize(initialText).then(resolve, reject);
+      };
+      this.socket.onerror = () => {
+        const error = new Error("Language server unavailable");
+        reject(error);
+        this.fail(error);
+      };
+      this.socket.onclose = () => {
+        const error = new Error("Language server disconnected");
+        reject(error);
+        this.fail(error);
+      };
+    });
+    // Callers handle failures per request; don't surface an unhandled rejection
+    this.ready.catch(() => {});
+  }
+
+  private send(message: object) {
+    this.socket.send(JSON.stringify({ jsonrpc: "2.0", ...message }));
+  }
+
+  private request(method: string, params: object): Promise<any> {
+    if (this.failure) return Promise.reject(this.failure);
+    const id = this.nextId++;
+    return new Promise((resolve, reject) => {
+      this.pending.set(id, { resolve, reject });
+      this.send({ id, method, params });
+    });
+  }
+
+  // Rejects every request still waiting, so callers don't hang forever
+  private fail(error: Error) {
+    if (!this.failure) this.failure = error;
+    this.pending.forEach(({ reject }) => reject(error));
+    this.pending.clear();
+  }
+
+  private notify(method: string, params: object) {
+    this.send({ method, params });
+  }
+
+  private handleMessage(data: string) {
+    const message = JSON.parse(data);
+    if (message.method && message.id !== undefined) {
+      // Server-to-client requests (progress, registrations) just need an ack
+      this.send({ id: message.id, result: null });
+    } else if (message.id !== undefined && this.pending.has(message.id)) {
+      this.pending.get(message.id)!.resolve(message.result);
+      this.pending.delete(message.id);
+    }
+  }
+
+  private async initialize(text: string) {
+    await this.request("initialize", {
+      processId: null,
+      rootUri: "file:///workspace",
+      capabilities: {
+        textDocument: {
+          completion: { completionItem: { documentationFormat: ["markdown"] } },
+          hover: { contentFormat: ["markdown"] },
+        },
+      },
+    });
+    this.notify("initialized", {});
+    this.notify("textDocument/didOpen", {
+      textDocument: {
+        uri: DOCUMENT_URI,
+        languageId: "rust",
+        version: this.version,
+        text,
+      },
+    });
+  }
+
+  async didChange(text: string) {
+    await this.ready;
+    this.notify("textDocument/didChange", {
+      textDocument: { uri: DOCUMENT_URI, version: ++this.version },
+      contentChanges: [{ text }],
+    });
+  }
+
+  async completion(position: Position): Promise<any> {
+    await this.ready;
+    return this.request("textDocument/completion", {
+      textDocument: { uri: DOCUMENT_URI },
+      position: {
+        line: position.lineNumber - 1,
+        character: position.column - 1,
+      },
+    });
+  }
+
+  async hover(position: Position): Promise<any> {
+    await this.ready;
+    return this.request("textDocument/hover", {
+      textDocument: { uri: DOCUMENT_URI },
+      position: {
+        line: position.lineNumber - 1,
+        character: position.column - 1,
+      },
+    });
+  }
+
+  close() {
+    this.socket.close();
+  }
+}
+
+function markdownValue(contents: any): string {
+  if (typeof contents === "string") return contents;
+  if (Array.isArray(contents)) return contents.map(markdownValue).join("\n\n");
+  return contents?.value ?? "";
+}
+
+// Wires completions and hover for the editor's model to rust-analyzer.
+// Returns a disposer that tears down the providers and the socket.
+export function connectRustAnalyzer(
+  monaco: Monaco,
+  editorInstance: EditorInstance,
+  lesson: string
+): () => void {
+  const model = editorInstance.getModel();
+  if (!model) return () => {};
+
+  const client = new RustAnalyzerClient(lesson, model.getValue());
+  const kinds = monaco.languages.CompletionItemKind;
+  // Indexed by LSP CompletionItemKind, which starts at Text = 1
+  const completionKinds = [
+    kinds.Text,
+    kinds.Text,
+    kinds.Method,
+    kinds.Function,
+    kinds.Constructor,
+    kinds.Field,
+    kinds.Variable,
+    kinds.Class,
+    kinds.Interface,
+    kinds.Module,
+    kinds.Property,
+    kinds.Unit,
+    kinds.Value,
+    kinds.Enum,
+    kinds.Keyword,
+    kinds.Snippet,
+    kinds.Color,
+    kinds.File,
+    kinds.Reference,
+    kinds.Folder,
+    kinds.EnumMember,
+    kinds.Constant,
+    kinds.Struct,
+    kinds.Event,
+    kinds.Operator,
+    kinds.TypeParameter,
+  ];
+  const disposables = [
+    model.onDidChangeContent(() => {
+      client.didChange(model.getValue()).catch(() => {});
+    }),
+    monaco.languages.registerCompletionItemProvider("rust", {
+      triggerCharacters: [":", "."],
+      async provideCompletionItems(target: any, position: Position) {
+        if (target !== model) return { suggestions: [] };
+        const result = await client.completion(position).catch(() => null);
+        const items = Array.isArray(result) ? result : result?.items ?? [];
+        const word = target.getWordUntilPosition(position);
+        const range = new monaco.Range(
+          position.lineNumber,
+          word.startColumn,
+          position.lineNumber,
+          word.endColumn
+        );
+        const suggestions = items.map(
+          (item: any) => ({
+            label: item.label,
+            kind: completionKinds[item.kind] ?? kinds.Text,
+            detail: item.detail,
+            documentation: item.documentation
+              ? { value: markdownValue(item.documentation) }
+              : undefined,
+            insertText: item.textEdit?.newText ?? item.insertText ?? item.label,
+            range,
+          })
+        );
+        return { suggestions };
+      },
+    }),
+    monaco.languages.registerHoverProvider("rust", {
+      async provideHover(target: any, position: Position) {
+        if (target !== model) return null;
+        const result = await client.hover(position).catch(() => null);
+        if (!result?.contents) return null;
+        return { contents: [{ value: markdownValue(result.contents) }] };
+      },
+    }),
+  ];
+
+  return () => {
+    disposables.forEach((disposable) => disposable.dispose());
+    client.close();
+  };
+}
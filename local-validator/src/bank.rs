@@ -0,0 +1,582 @@
+use arch_program::{
+    account::AccountInfo, entrypoint::ProgramResult, hash::Hash, pubkey::Pubkey,
+    sanitized::ArchMessage,
+};
+use arch_sdk::{RuntimeTransaction, Status};
+use bitcoin::hashes::{sha256, Hash as _};
+use bitcoin::OutPoint;
+use std::collections::{HashMap, VecDeque};
+
+use crate::{bip322, runtime, syscalls, system_program};
+
+/// Signature of a program's `process_instruction`, registered natively.
+pub type ProcessInstruction = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+/// Room left after each account's data so programs can grow accounts with
+/// `realloc`, matching the on-chain loader.
+pub const MAX_PERMITTED_DATA_INCREASE: usize = 10 * 1024;
+
+/// How many recent block hashes a transaction may reference.
+const RECENT_BLOCK_HASHES: usize = 150;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+    pub is_executable: bool,
+}
+
+impl Account {
+    pub fn new(lamports: u64, space: usize, owner: &Pubkey) -> Self {
+        Account {
+            lamports,
+            data: vec![0; space],
+            owner: *owner,
+            is_executable: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ProcessedTransaction {
+    pub txid: String,
+    pub runtime_transaction: RuntimeTransaction,
+    pub status: Status,
+    pub logs: Vec<String>,
+}
+
+/// Result of one top-level instruction in a message.
+#[derive(Clone, Debug)]
+pub struct InstructionOutcome {
+    pub program_id: Pubkey,
+    pub result: Result<(), String>,
+}
+
+/// What running a message produced: results for the instructions that ran
+/// and the post-execution state of every account the message references.
+#[derive(Clone, Debug)]
+pub struct MessageOutcome {
+    pub instructions: Vec<InstructionOutcome>,
+    pub accounts: Vec<(Pubkey, Account)>,
+}
+
+impl MessageOutcome {
+    /// The failed instruction's index and error, if any instruction failed.
+    pub fn error(&self) -> Option<(usize, &str)> {
+        self.instructions
+            .iter()
+            .enumerate()
+            .find_map(|(index, instruction)| match &instruction.result {
+                Ok(()) => None,
+                Err(err) => Some((index, err.as_str())),
+            })
+    }
+}
+
+/// In-memory ledger: accounts, native programs and a chain of block hashes.
+pub struct Bank {
+    accounts: HashMap<Pubkey, Account>,
+    programs: HashMap<Pubkey, ProcessInstruction>,
+    block_height: u64,
+    recent_block_hashes: VecDeque<[u8; 32]>,
+    processed_transactions: HashMap<String, ProcessedTransaction>,
+    /// The Bitcoin output each account is anchored to, if any.
+    utxos: HashMap<Pubkey, OutPoint>,
+}
+
+impl Default for Bank {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bank {
+    pub fn new() -> Self {
+        syscalls::install();
+
+        let mut bank = Bank {
+            accounts: HashMap::new(),
+            programs: HashMap::new(),
+            block_height: 0,
+            recent_block_hashes: VecDeque::from([sha256::Hash::hash(b"genesis").to_byte_array()]),
+            processed_transactions: HashMap::new(),
+            utxos: HashMap::new(),
+        };
+        bank.add_program(&Pubkey::system_program(), system_program::process_instruction);
+        bank
+    }
+
+    /// Registers a natively compiled program and marks its account executable.
+    pub fn add_program(&mut self, program_id: &Pubkey, entrypoint: ProcessInstruction) {
+        self.programs.insert(*program_id, entrypoint);
+        self.accounts.insert(
+            *program_id,
+            Account {
+                lamports: 1,
+                data: Vec::new(),
+                owner: Pubkey::system_program(),
+                is_executable: true,
+            },
+        );
+    }
+
+    pub fn get_account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn set_account(&mut self, pubkey: &Pubkey, account: Account) {
+        self.accounts.insert(*pubkey, account);
+    }
+
+    /// Credits lamports to an account, creating it as a system account.
+    pub fn airdrop(&mut self, pubkey: &Pubkey, lamports: u64) {
+        let account = self
+            .accounts
+            .entry(*pubkey)
+            .or_insert_with(|| Account::new(0, 0, &Pubkey::system_program()));
+        account.lamports = account.lamports.saturating_add(lamports);
+    }
+
+    /// Ties an account to a regtest UTXO sent to its address.
+    pub fn anchor_account(&mut self, pubkey: &Pubkey, utxo: OutPoint) {
+        self.utxos.insert(*pubkey, utxo);
+    }
+
+    pub fn account_utxo(&self, pubkey: &Pubkey) -> Option<OutPoint> {
+        self.utxos.get(pubkey).copied()
+    }
+
+    pub fn block_height(&self) -> u64 {
+        self.block_height
+    }
+
+    pub fn best_block_hash(&self) -> Hash {
+        Hash::from(self.best_block_hash_bytes())
+    }
+
+    /// The best block hash as the node API returns it.
+    pub fn best_block_hash_hex(&self) -> String {
+        hex::encode(self.best_block_hash_bytes())
+    }
+
+    fn best_block_hash_bytes(&self) -> [u8; 32] {
+        *self
+            .recent_block_hashes
+            .back()
+            .expect("genesis hash is never evicted")
+    }
+
+    pub fn get_processed_transaction(&self, txid: &str) -> Option<&ProcessedTransaction> {
+        self.processed_transactions.get(txid)
+    }
+
+    /// Processes transactions in order and seals them into a new block.
+    pub fn process_transactions(
+        &mut self,
+        transactions: Vec<RuntimeTransaction>,
+    ) -> Vec<ProcessedTransaction> {
+        let processed: Vec<_> = transactions
+            .into_iter()
+            .enumerate()
+            .map(|(index, transaction)| self.process_transaction(index, transaction))
+            .collect();
+
+        let mut block = self.best_block_hash_bytes().to_vec();
+        for transaction in &processed {
+            block.extend_from_slice(transaction.txid.as_bytes());
+        }
+        self.block_height += 1;
+        self.recent_block_hashes
+            .push_back(sha256::Hash::hash(&block).to_byte_array());
+        if self.recent_block_hashes.len() > RECENT_BLOCK_HASHES {
+            self.recent_block_hashes.pop_front();
+        }
+
+        for transaction in &processed {
+            self.processed_transactions
+                .insert(transaction.txid.clone(), transaction.clone());
+        }
+        processed
+    }
+
+    fn process_transaction(
+        &mut self,
+        index: usize,
+        transaction: RuntimeTransaction,
+    ) -> ProcessedTransaction {
+        let txid = transaction_id(self.block_height, index, &transaction);
+        let mut logs = Vec::new();
+
+        let result = self
+            .verify_transaction(&transaction)
+            .and_then(|()| self.execute_message(&transaction.message, &mut logs));
+
+        let status = match result {
+            Ok(()) => Status::Processed,
+            Err(err) => Status::Failed(err),
+        };
+
+        ProcessedTransaction {
+            txid,
+            runtime_transaction: transaction,
+            status,
+            logs,
+        }
+    }
+
+    fn verify_transaction(&self, transaction: &RuntimeTransaction) -> Result<(), String> {
+        let message = &transaction.message;
+        let known = self
+            .recent_block_hashes
+            .iter()
+            .any(|hash| Hash::from(*hash) == message.recent_blockhash);
+        if !known {
+            return Err("Block hash not found".to_string());
+        }
+
+        sanitize_message(message)?;
+
+        // Every required signer needs a signature of the message hash by its
+        // key. arch_sdk signs in the order its caller lists the keypairs,
+        // which needn't match the account order, so any signature may match
+        let message_hash = message.hash();
+        let required = message.header.num_required_signatures as usize;
+        for signer in &message.account_keys[..required] {
+            let signer_key = signer.serialize();
+            let signed = transaction
+                .signatures
+                .iter()
+                .any(|signature| bip322::verify(&message_hash, &signer_key, &signature.0));
+            if !signed {
+                return Err(format!("Missing or invalid signature for {}", signer));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs every instruction in a message against copies of its accounts,
+    /// committing them only if all instructions succeed.
+    pub fn execute_message(
+        &mut self,
+        message: &ArchMessage,
+        logs: &mut Vec<String>,
+    ) -> Result<(), String> {
+        let outcome = self.simulate_message(message, logs)?;
+        if let Some((index, err)) = outcome.error() {
+            return Err(format!("Instruction {} failed: {}", index, err));
+        }
+        for (key, account) in outcome.accounts {
+            self.accounts.insert(key, account);
+        }
+        Ok(())
+    }
+
+    /// Runs a message without committing it, stopping at the first failed
+    /// instruction, and returns each instruction's result along with the
+    /// accounts as they'd be afterwards. Fails without running anything if
+    /// the message is malformed.
+    pub fn simulate_message(
+        &self,
+        message: &ArchMessage,
+        logs: &mut Vec<String>,
+    ) -> Result<MessageOutcome, String> {
+        sanitize_message(message)?;
+
+        let keys = &message.account_keys;
+        let loaded: Vec<Account> = keys
+            .iter()
+            .map(|key| {
+                self.accounts
+                    .get(key)
+                    .cloned()
+                    .unwrap_or_else(|| Account::new(0, 0, &Pubkey::system_program()))
+            })
+            .collect();
+
+        let mut lamports: Vec<u64> = loaded.iter().map(|account| account.lamports).collect();
+        let mut owners: Vec<Pubkey> = loaded.iter().map(|account| account.owner).collect();
+        // Each buffer is laid out like the loader's input: the data length,
+        // then the data, then spare room for realloc
+        let mut buffers: Vec<Vec<u8>> = loaded
+            .iter()
+            .map(|account| {
+                let mut buffer = (account.data.len() as u64).to_le_bytes().to_vec();
+                buffer.extend_from_slice(&account.data);
+                buffer.resize(buffer.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+                buffer
+            })
+            .collect();
+
+        let mut instructions = Vec::new();
+        {
+            let account_infos: Vec<AccountInfo> = keys
+                .iter()
+                .zip(lamports.iter_mut())
+                .zip(buffers.iter_mut())
+                .zip(owners.iter_mut())
+                .enumerate()
+                .map(|(index, (((key, lamports), buffer), owner))| {
+                    let data_len = loaded[index].data.len();
+                    AccountInfo::new(
+                        key,
+                        is_signer(message, index),
+                        is_writable(message, index),
+                        lamports,
+                        &mut buffer[8..8 + data_len],
+                        owner,
+                        loaded[index].is_executable,
+                        0,
+                    )
+                })
+                .collect();
+
+            runtime::with_programs(&self.programs, || {
+                for instruction in &message.instructions {
+                    let program_id = keys[instruction.program_id_index as usize];
+                    let instruction_accounts: Vec<AccountInfo> = instruction
+                        .accounts
+                        .iter()
+                        .map(|&account_index| account_infos[account_index as usize].clone())
+                        .collect();
+
+                    let result = runtime::process_instruction(
+                        &program_id,
+                        &instruction_accounts,
+                        &instruction.data,
+                    );
+                    logs.extend(syscalls::take_logs());
+                    let failed = result.is_err();
+                    instructions.push(InstructionOutcome { program_id, result });
+                    if failed {
+                        break;
+                    }
+                }
+            });
+        }
+
+        let accounts = keys
+            .iter()
+            .enumerate()
+            .map(|(index, key)| {
+                let mut length = [0u8; 8];
+                length.copy_from_slice(&buffers[index][..8]);
+                let data_len = u64::from_le_bytes(length) as usize;
+                let account = Account {
+                    lamports: lamports[index],
+                    data: buffers[index][8..8 + data_len].to_vec(),
+                    owner: owners[index],
+                    is_executable: loaded[index].is_executable,
+                };
+                (*key, account)
+            })
+            .collect();
+
+        Ok(MessageOutcome {
+            instructions,
+            accounts,
+        })
+    }
+}
+
+/// Rejects a message whose header or instructions point past its account
+/// keys. Messages from `ArchMessage::new` always pass, but ones decoded from
+/// the wire can claim anything, and everything after this indexes freely.
+pub fn sanitize_message(message: &ArchMessage) -> Result<(), String> {
+    let header = &message.header;
+    let num_keys = message.account_keys.len();
+    let num_signed = header.num_required_signatures as usize;
+    if num_signed > num_keys {
+        return Err(format!(
+            "Message requires {} signatures but has only {} account keys",
+            num_signed, num_keys
+        ));
+    }
+    if header.num_readonly_signed_accounts as usize > num_signed {
+        return Err(format!(
+            "Message marks {} signed accounts read-only but requires only {} signatures",
+            header.num_readonly_signed_accounts, num_signed
+        ));
+    }
+    if header.num_readonly_unsigned_accounts as usize > num_keys - num_signed {
+        return Err(format!(
+            "Message marks {} unsigned accounts read-only but has only {}",
+            header.num_readonly_unsigned_accounts,
+            num_keys - num_signed
+        ));
+    }
+
+    for (index, instruction) in message.instructions.iter().enumerate() {
+        let out_of_range = std::iter::once(&instruction.program_id_index)
+            .chain(&instruction.accounts)
+            .find(|&&key_index| key_index as usize >= num_keys);
+        if let Some(key_index) = out_of_range {
+            return Err(format!(
+                "Instruction {} refers to account {}, but the message has only {} account keys",
+                index, key_index, num_keys
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn is_signer(message: &ArchMessage, index: usize) -> bool {
+    index < message.header.num_required_signatures as usize
+}
+
+fn is_writable(message: &ArchMessage, index: usize) -> bool {
+    let header = &message.header;
+    let num_signed = header.num_required_signatures as usize;
+    if index < num_signed {
+        index < num_signed - header.num_readonly_signed_accounts as usize
+    } else {
+        index < message.account_keys.len() - header.num_readonly_unsigned_accounts as usize
+    }
+}
+
+fn transaction_id(block_height: u64, index: usize, transaction: &RuntimeTransaction) -> String {
+    let mut preimage = block_height.to_le_bytes().to_vec();
+    preimage.extend_from_slice(&(index as u64).to_le_bytes());
+    preimage.extend_from_slice(&transaction.message.serialize());
+    hex::encode(sha256::Hash::hash(&preimage).to_byte_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_program::system_instruction;
+    use arch_sdk::{build_and_sign_transaction, generate_new_keypair};
+    use bitcoin::{key::Keypair, Network};
+
+    const NETWORK: Network = Network::Regtest;
+
+    fn funded_keypair(bank: &mut Bank) -> (Keypair, Pubkey) {
+        let (keypair, pubkey, _) = generate_new_keypair(NETWORK);
+        bank.airdrop(&pubkey, 1_000);
+        (keypair, pubkey)
+    }
+
+    fn transfer_message(bank: &Bank, from: &Pubkey, to: &Pubkey) -> ArchMessage {
+        ArchMessage::new(
+            &[system_instruction::transfer(from, to, 400)],
+            Some(*from),
+            bank.best_block_hash(),
+        )
+    }
+
+    fn process(bank: &mut Bank, transaction: RuntimeTransaction) -> ProcessedTransaction {
+        bank.process_transactions(vec![transaction]).remove(0)
+    }
+
+    fn assert_failed_with(status: &Status, expected: &str) {
+        match status {
+            Status::Failed(message) => assert!(
+                message.contains(expected),
+                "expected an error containing {:?}, got {:?}",
+                expected,
+                message
+            ),
+            status => panic!("expected failure containing {:?}, got {:?}", expected, status),
+        }
+    }
+
+    #[test]
+    fn signed_transfer_is_processed() {
+        let mut bank = Bank::new();
+        let (payer, payer_pubkey) = funded_keypair(&mut bank);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+
+        let message = transfer_message(&bank, &payer_pubkey, &recipient);
+        let processed = process(&mut bank, build_and_sign_transaction(message, vec![payer], NETWORK));
+
+        assert_eq!(processed.status, Status::Processed);
+        assert_eq!(bank.get_account(&payer_pubkey).map(|account| account.lamports), Some(600));
+        assert_eq!(bank.get_account(&recipient).map(|account| account.lamports), Some(400));
+    }
+
+    #[test]
+    fn signature_from_wrong_key_is_rejected() {
+        let mut bank = Bank::new();
+        let (_, payer_pubkey) = funded_keypair(&mut bank);
+        let (impostor, _, _) = generate_new_keypair(NETWORK);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+
+        let message = transfer_message(&bank, &payer_pubkey, &recipient);
+        let processed = process(&mut bank, build_and_sign_transaction(message, vec![impostor], NETWORK));
+
+        assert_failed_with(&processed.status, "Missing or invalid signature");
+        assert_eq!(bank.get_account(&payer_pubkey).map(|account| account.lamports), Some(1_000));
+    }
+
+    #[test]
+    fn signature_over_another_message_is_rejected() {
+        let mut bank = Bank::new();
+        let (payer, payer_pubkey) = funded_keypair(&mut bank);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+
+        let signed = build_and_sign_transaction(
+            transfer_message(&bank, &payer_pubkey, &payer_pubkey),
+            vec![payer],
+            NETWORK,
+        );
+        let transaction = RuntimeTransaction {
+            message: transfer_message(&bank, &payer_pubkey, &recipient),
+            ..signed
+        };
+
+        assert_failed_with(&process(&mut bank, transaction).status, "Missing or invalid signature");
+    }
+
+    #[test]
+    fn unsigned_transaction_is_rejected() {
+        let mut bank = Bank::new();
+        let (_, payer_pubkey) = funded_keypair(&mut bank);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+
+        let message = transfer_message(&bank, &payer_pubkey, &recipient);
+        let processed = process(&mut bank, build_and_sign_transaction(message, vec![], NETWORK));
+
+        assert_failed_with(&processed.status, "Missing or invalid signature");
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let bank = Bank::new();
+        let (_, payer, _) = generate_new_keypair(NETWORK);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+        let message = transfer_message(&bank, &payer, &recipient);
+        assert_eq!(sanitize_message(&message), Ok(()));
+
+        let mut too_many_signers = message.clone();
+        too_many_signers.header.num_required_signatures = 4;
+        assert!(sanitize_message(&too_many_signers).is_err());
+
+        // Would underflow the writable check
+        let mut readonly_signers = message.clone();
+        readonly_signers.header.num_readonly_signed_accounts = 2;
+        assert!(sanitize_message(&readonly_signers).is_err());
+
+        let mut readonly_unsigned = message.clone();
+        readonly_unsigned.header.num_readonly_unsigned_accounts = 3;
+        assert!(sanitize_message(&readonly_unsigned).is_err());
+    }
+
+    #[test]
+    fn out_of_range_indexes_are_rejected() {
+        let mut bank = Bank::new();
+        let (payer, payer_pubkey) = funded_keypair(&mut bank);
+        let (_, recipient, _) = generate_new_keypair(NETWORK);
+
+        let mut bad_program = transfer_message(&bank, &payer_pubkey, &recipient);
+        bad_program.instructions[0].program_id_index = 3;
+        assert!(bank.simulate_message(&bad_program, &mut Vec::new()).is_err());
+
+        let mut bad_account = transfer_message(&bank, &payer_pubkey, &recipient);
+        bad_account.instructions[0].accounts.push(200);
+        assert!(bank.simulate_message(&bad_account, &mut Vec::new()).is_err());
+
+        // Through the bank, a malformed message fails instead of panicking
+        let processed = process(&mut bank, build_and_sign_transaction(bad_account, vec![payer], NETWORK));
+        assert_failed_with(&processed.status, "refers to account 200");
+    }
+}
@@ -0,0 +1,88 @@
+// ============================================================================
+// LOCAL NODE - Arch JSON-RPC Emulator
+// ============================================================================
+// Serves the node API subset lesson tests use from the local bank, so
+// unmodified ArchRpcClient code can point at localhost, and a bitcoind-style
+// API over the local regtest chain for bitcoincore-rpc. Lesson programs are
+// compiled in natively; enable them with features, e.g.
+//
+//     cargo run --features node,token-lesson --bin local-node
+
+use axum::{routing::post, Json, Router};
+use local_validator::bitcoin_rpc;
+use local_validator::rpc::{self, RpcRequest, RpcResponse};
+use std::net::SocketAddr;
+use tracing::info;
+
+/// Same port as `arch_test_sdk`'s `NODE1_ADDRESS`.
+const DEFAULT_PORT: u16 = 9002;
+
+/// bitcoind's default regtest RPC port.
+const DEFAULT_BITCOIN_PORT: u16 = 18443;
+
+async fn handle_rpc(Json(request): Json<RpcRequest>) -> Json<RpcResponse> {
+    info!("rpc: {}", request.method);
+    Json(rpc::handle(request))
+}
+
+async fn handle_bitcoin_rpc(Json(request): Json<RpcRequest>) -> Json<RpcResponse> {
+    info!("bitcoin rpc: {}", request.method);
+    Json(bitcoin_rpc::handle(request))
+}
+
+fn port_from_env(name: &str, default: u16) -> u16 {
+    std::env::var(name)
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(default)
+}
+
+fn register_lesson_programs() {
+    #[cfg(feature = "token-lesson")]
+    {
+        use arch_program::pubkey::Pubkey;
+
+        let program_id = Pubkey::from_slice(b"bitcoin_zombies_token_operations");
+        local_validator::helper::register_program(
+            &program_id,
+            token_operations::process_instruction,
+        );
+        info!("Registered token_operations at {}", program_id);
+
+        let program_id = Pubkey::from_slice(b"bitcoin_zombies_associated_token");
+        local_validator::helper::register_program(
+            &program_id,
+            associated_token_account::process_instruction,
+        );
+        info!("Registered associated_token_account at {}", program_id);
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt::init();
+    register_lesson_programs();
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port_from_env("LOCAL_NODE_PORT", DEFAULT_PORT)));
+    let bitcoin_addr = SocketAddr::from((
+        [127, 0, 0, 1],
+        port_from_env("LOCAL_BITCOIN_PORT", DEFAULT_BITCOIN_PORT),
+    ));
+
+    let app = Router::new().route("/", post(handle_rpc));
+    // Wallet clients post to /wallet/<name>; there's a single wallet
+    let bitcoin_app = Router::new()
+        .route("/", post(handle_bitcoin_rpc))
+        .route("/wallet/:name", post(handle_bitcoin_rpc));
+
+    info!("Local Arch node listening on http://{}", addr);
+    info!("Local bitcoind (regtest) listening on http://{}", bitcoin_addr);
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let bitcoin_listener = tokio::net::TcpListener::bind(bitcoin_addr).await.unwrap();
+    let (node, bitcoin) = tokio::join!(
+        axum::serve(listener, app),
+        axum::serve(bitcoin_listener, bitcoin_app),
+    );
+    node.unwrap();
+    bitcoin.unwrap();
+}
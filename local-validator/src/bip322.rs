@@ -0,0 +1,108 @@
+use bitcoin::{
+    absolute::LockTime,
+    hashes::{sha256, Hash, HashEngine},
+    key::{Secp256k1, XOnlyPublicKey},
+    opcodes::{all::OP_RETURN, OP_0},
+    script::Builder,
+    secp256k1::{schnorr, Message},
+    sighash::{Prevouts, SighashCache, TapSighashType},
+    taproot::TapTweakHash,
+    transaction::Version,
+    Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Witness,
+};
+
+// ============================================================================
+// BIP-322 - Verifying Transaction Signatures
+// ============================================================================
+// arch_sdk signs a transaction by signing its message hash as a BIP-322
+// "simple" message with each signer's taproot key. Verifying one rebuilds
+// the two virtual transactions BIP-322 defines and checks the signature
+// against the key-spend sighash of the second.
+
+const MESSAGE_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// Whether `signature` is a BIP-322 signature of `message` by the taproot
+/// key whose internal key is `signer`. The 64-byte signature doesn't say
+/// which sighash type it commits to, so both key-spend types are accepted.
+pub fn verify(message: &[u8], signer: &[u8; 32], signature: &[u8]) -> bool {
+    let (Ok(internal_key), Ok(signature)) = (
+        XOnlyPublicKey::from_slice(signer),
+        schnorr::Signature::from_slice(signature),
+    ) else {
+        return false;
+    };
+
+    let secp = Secp256k1::verification_only();
+    let script_pubkey = ScriptBuf::new_p2tr(&secp, internal_key, None);
+    let tweak = TapTweakHash::from_key_and_tweak(internal_key, None).to_scalar();
+    let Ok((output_key, _)) = internal_key.add_tweak(&secp, &tweak) else {
+        return false;
+    };
+
+    let to_spend = to_spend(message, script_pubkey);
+    let to_sign = to_sign(&to_spend);
+    let prevouts = [&to_spend.output[0]];
+
+    [TapSighashType::All, TapSighashType::Default]
+        .into_iter()
+        .any(|sighash_type| {
+            let Ok(sighash) = SighashCache::new(&to_sign).taproot_key_spend_signature_hash(
+                0,
+                &Prevouts::All(&prevouts),
+                sighash_type,
+            ) else {
+                return false;
+            };
+            let digest = Message::from_digest(sighash.to_byte_array());
+            secp.verify_schnorr(&signature, &digest, &output_key).is_ok()
+        })
+}
+
+/// Tagged hash of the message, committed to by `to_spend`'s input script.
+fn message_hash(message: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(MESSAGE_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(tag.as_byte_array());
+    engine.input(tag.as_byte_array());
+    engine.input(message);
+    sha256::Hash::from_engine(engine).to_byte_array()
+}
+
+/// The virtual transaction that "pays" the signer's address.
+fn to_spend(message: &[u8], script_pubkey: ScriptBuf) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Builder::new()
+                .push_opcode(OP_0)
+                .push_slice(message_hash(message))
+                .into_script(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey,
+        }],
+    }
+}
+
+/// The virtual transaction spending `to_spend`, whose sighash is signed.
+fn to_sign(to_spend: &Transaction) -> Transaction {
+    Transaction {
+        version: Version(0),
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(to_spend.compute_txid(), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ZERO,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::ZERO,
+            script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+        }],
+    }
+}
@@ -0,0 +1,287 @@
+use bitcoin::consensus::encode::serialize_hex;
+use bitcoin::{Address, Amount, BlockHash, Network, OutPoint, TxOut, Txid};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+use crate::helper;
+use crate::regtest::{self, Regtest};
+use crate::rpc::{self, RpcRequest, RpcResponse};
+
+// bitcoind's error codes, so bitcoincore-rpc reports them the same way
+const RPC_MISC_ERROR: i64 = -1;
+const RPC_INVALID_ADDRESS_OR_KEY: i64 = -5;
+const RPC_WALLET_INSUFFICIENT_FUNDS: i64 = -6;
+const RPC_INVALID_PARAMETER: i64 = -8;
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+
+/// Most blocks one `generatetoaddress` call may mine, so a typo can't stall
+/// the node building millions of blocks.
+pub const MAX_GENERATE_BLOCKS: u64 = 1_000;
+
+/// Answers a bitcoind JSON-RPC call from the shared regtest chain.
+pub fn handle(request: RpcRequest) -> RpcResponse {
+    rpc::respond(request.id, dispatch(&request.method, &request.params))
+}
+
+/// The wallet and chain calls `bitcoincore-rpc` clients make in lessons and
+/// arch_test_sdk: funding addresses, mining, and looking up transactions,
+/// outputs and confirmations. Params are positional, as bitcoind takes them.
+pub fn dispatch(method: &str, params: &Value) -> Result<Value, rpc::RpcError> {
+    match method {
+        "getblockchaininfo" => {
+            let regtest = helper::regtest();
+            Ok(json!({
+                "chain": "regtest",
+                "blocks": regtest.block_count(),
+                "headers": regtest.block_count(),
+                "bestblockhash": regtest.best_block_hash(),
+            }))
+        }
+        "getnetworkinfo" => Ok(json!({ "version": 270000, "subversion": "/local-validator/" })),
+        "getblockcount" => Ok(json!(helper::regtest().block_count())),
+        "getbestblockhash" => Ok(json!(helper::regtest().best_block_hash())),
+        "getblockhash" => {
+            let height: u64 = required(params, 0)?;
+            helper::regtest()
+                .block_hash(height)
+                .map(|hash| json!(hash))
+                .ok_or_else(|| error(RPC_INVALID_PARAMETER, "Block height out of range".to_string()))
+        }
+        "getblock" => {
+            let hash: BlockHash = required(params, 0)?;
+            if optional::<u64>(params, 1)? == Some(0) {
+                return Err(error(
+                    RPC_INVALID_PARAMETER,
+                    "Serialized blocks aren't available on the local chain; use verbosity 1".to_string(),
+                ));
+            }
+            let regtest = helper::regtest();
+            let (height, txids) = regtest
+                .get_block(&hash)
+                .ok_or_else(|| error(RPC_INVALID_ADDRESS_OR_KEY, "Block not found".to_string()))?;
+            Ok(json!({
+                "hash": hash,
+                "height": height,
+                "confirmations": regtest.block_count() - height + 1,
+                "previousblockhash": height.checked_sub(1).and_then(|prev| regtest.block_hash(prev)),
+                "tx": txids,
+            }))
+        }
+        "getnewaddress" | "getrawchangeaddress" => {
+            Ok(json!(helper::regtest().wallet_address().to_string()))
+        }
+        "getbalance" => Ok(json!(helper::regtest().balance().to_btc())),
+        "sendtoaddress" => {
+            let address = address_param(params, 0)?;
+            let amount = Amount::from_btc(required(params, 1)?)
+                .map_err(|e| error(RPC_INVALID_PARAMETER, format!("Invalid amount: {}", e)))?;
+            let txid = helper::regtest()
+                .send_to_address(&address, amount)
+                .map_err(|err| error(RPC_WALLET_INSUFFICIENT_FUNDS, err))?;
+            Ok(json!(txid))
+        }
+        "generatetoaddress" => {
+            let count: u64 = required(params, 0)?;
+            if count > MAX_GENERATE_BLOCKS {
+                return Err(error(
+                    RPC_INVALID_PARAMETER,
+                    format!("Cannot mine more than {} blocks at once", MAX_GENERATE_BLOCKS),
+                ));
+            }
+            let address = address_param(params, 1)?;
+            Ok(json!(helper::regtest().mine_blocks(count, &address)))
+        }
+        "getrawmempool" => Ok(json!(helper::regtest().mempool())),
+        "getrawtransaction" | "gettransaction" => {
+            let txid: Txid = required(params, 0)?;
+            let verbose = method == "gettransaction"
+                || optional::<Value>(params, 1)?.is_some_and(|verbose| {
+                    verbose.as_bool() == Some(true) || verbose.as_u64().is_some_and(|level| level > 0)
+                });
+            let regtest = helper::regtest();
+            let transaction = regtest.get_transaction(&txid).ok_or_else(|| {
+                error(RPC_INVALID_ADDRESS_OR_KEY, "No such mempool or blockchain transaction".to_string())
+            })?;
+            if !verbose {
+                return Ok(json!(serialize_hex(transaction)));
+            }
+            let outputs: Vec<Value> = transaction
+                .output
+                .iter()
+                .enumerate()
+                .map(|(vout, txout)| {
+                    let mut output = txout_json(txout);
+                    output["n"] = json!(vout);
+                    output
+                })
+                .collect();
+            Ok(json!({
+                "txid": txid,
+                "hex": serialize_hex(transaction),
+                "confirmations": regtest.confirmations(&txid),
+                "vout": outputs,
+            }))
+        }
+        "gettxout" => {
+            let txid: Txid = required(params, 0)?;
+            let vout: u32 = required(params, 1)?;
+            let regtest = helper::regtest();
+            Ok(regtest
+                .get_tx_out(&OutPoint::new(txid, vout))
+                .map(|utxo| {
+                    let mut output = txout_json(&utxo.txout);
+                    output["bestblock"] = json!(regtest.best_block_hash());
+                    output["confirmations"] = json!(regtest.confirmations_of(utxo));
+                    output["coinbase"] = json!(utxo.is_coinbase);
+                    output
+                })
+                .unwrap_or(Value::Null))
+        }
+        "listunspent" => {
+            let min_confirmations = optional::<u64>(params, 0)?.unwrap_or(1);
+            let max_confirmations = optional::<u64>(params, 1)?.unwrap_or(9_999_999);
+            let addresses: Vec<String> = optional(params, 2)?.unwrap_or_default();
+            let regtest = helper::regtest();
+            let addresses = if addresses.is_empty() {
+                vec![regtest.wallet_address().clone()]
+            } else {
+                addresses
+                    .iter()
+                    .map(|address| regtest::parse_address(address))
+                    .collect::<Result<_, _>>()
+                    .map_err(|err| error(RPC_INVALID_ADDRESS_OR_KEY, err))?
+            };
+            Ok(json!(list_unspent(&regtest, &addresses, min_confirmations..=max_confirmations)))
+        }
+        _ => Err(error(RPC_METHOD_NOT_FOUND, format!("Method not found: {}", method))),
+    }
+}
+
+fn list_unspent(
+    regtest: &Regtest,
+    addresses: &[Address],
+    confirmations: std::ops::RangeInclusive<u64>,
+) -> Vec<Value> {
+    addresses
+        .iter()
+        .flat_map(|address| regtest.list_unspent(address))
+        .filter(|utxo| confirmations.contains(&regtest.confirmations_of(utxo)))
+        .map(|utxo| {
+            let mut output = txout_json(&utxo.txout);
+            output["txid"] = json!(utxo.outpoint.txid);
+            output["vout"] = json!(utxo.outpoint.vout);
+            output["amount"] = output["value"].take();
+            output["confirmations"] = json!(regtest.confirmations_of(utxo));
+            output["spendable"] = json!(true);
+            output
+        })
+        .collect()
+}
+
+fn txout_json(txout: &TxOut) -> Value {
+    let address = Address::from_script(&txout.script_pubkey, Network::Regtest)
+        .ok()
+        .map(|address| address.to_string());
+    json!({
+        "value": txout.value.to_btc(),
+        "scriptPubKey": {
+            "hex": txout.script_pubkey.to_hex_string(),
+            "address": address,
+        },
+    })
+}
+
+fn address_param(params: &Value, index: usize) -> Result<Address, rpc::RpcError> {
+    let address: String = required(params, index)?;
+    regtest::parse_address(&address).map_err(|err| error(RPC_INVALID_ADDRESS_OR_KEY, err))
+}
+
+fn required<T: DeserializeOwned>(params: &Value, index: usize) -> Result<T, rpc::RpcError> {
+    optional(params, index)?
+        .ok_or_else(|| error(RPC_MISC_ERROR, format!("Missing parameter {}", index)))
+}
+
+fn optional<T: DeserializeOwned>(params: &Value, index: usize) -> Result<Option<T>, rpc::RpcError> {
+    match params.get(index) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| error(RPC_INVALID_PARAMETER, format!("Invalid parameter {}: {}", index, e))),
+    }
+}
+
+fn error(code: i64, message: String) -> rpc::RpcError {
+    rpc::RpcError { code, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_sdk::generate_new_keypair;
+    use bitcoin::hashes::Hash as _;
+
+    /// A fresh address, so tests sharing the chain don't see each other's
+    /// outputs.
+    fn new_address() -> String {
+        let (_, pubkey, _) = generate_new_keypair(Network::Regtest);
+        regtest::account_address(&pubkey).unwrap().to_string()
+    }
+
+    fn call(method: &str, params: Value) -> Value {
+        dispatch(method, &params).unwrap_or_else(|err| panic!("{} failed: {}", method, err.message))
+    }
+
+    #[test]
+    fn payments_confirm_as_blocks_are_mined() {
+        let address = new_address();
+        let wallet = call("getnewaddress", json!([]));
+
+        let txid = call("sendtoaddress", json!([address, 0.0001]));
+        assert!(call("getrawmempool", json!([])).as_array().unwrap().contains(&txid));
+        assert_eq!(call("gettransaction", json!([txid]))["confirmations"], 0);
+        assert_eq!(call("listunspent", json!([0, 0, [address]])).as_array().unwrap().len(), 1);
+
+        let blocks = call("generatetoaddress", json!([2, wallet]));
+        let block = call("getblock", json!([blocks[0]]));
+        assert!(block["tx"].as_array().unwrap().contains(&txid));
+        assert!(block["confirmations"].as_u64().unwrap() >= 2);
+
+        // Other tests may mine on the shared chain too
+        let transaction = call("getrawtransaction", json!([txid, true]));
+        assert!(transaction["confirmations"].as_u64().unwrap() >= 2);
+        assert_eq!(transaction["vout"][0]["scriptPubKey"]["address"], address);
+
+        let output = call("gettxout", json!([txid, 0]));
+        assert_eq!(output["value"], 0.0001);
+        assert_eq!(output["coinbase"], false);
+        assert!(output["confirmations"].as_u64().unwrap() >= 2);
+
+        let unspent = call("listunspent", json!([1, 9_999_999, [address]]));
+        assert_eq!(unspent[0]["txid"], txid);
+        assert_eq!(unspent[0]["amount"], 0.0001);
+    }
+
+    #[test]
+    fn bad_requests_get_bitcoind_error_codes() {
+        let error_code = |method: &str, params: Value| dispatch(method, &params).unwrap_err().code;
+
+        assert_eq!(error_code("sendtoaddress", json!(["not an address", 1.0])), RPC_INVALID_ADDRESS_OR_KEY);
+        assert_eq!(error_code("sendtoaddress", json!([new_address(), 1_000_000.0])), RPC_WALLET_INSUFFICIENT_FUNDS);
+        assert_eq!(error_code("getrawtransaction", json!([Txid::all_zeros()])), RPC_INVALID_ADDRESS_OR_KEY);
+        assert_eq!(error_code("getblockhash", json!([u64::MAX])), RPC_INVALID_PARAMETER);
+        assert_eq!(error_code("generatetoaddress", json!([])), RPC_MISC_ERROR);
+        assert_eq!(error_code("getbestblock", json!([])), RPC_METHOD_NOT_FOUND);
+    }
+
+    #[test]
+    fn unknown_outputs_are_null() {
+        assert_eq!(call("gettxout", json!([Txid::all_zeros(), 0])), Value::Null);
+    }
+
+    #[test]
+    fn generatetoaddress_is_capped() {
+        let address = helper::regtest().wallet_address().to_string();
+        let err = dispatch("generatetoaddress", &json!([MAX_GENERATE_BLOCKS + 1, address])).unwrap_err();
+        assert_eq!(err.code, RPC_INVALID_PARAMETER);
+    }
+}
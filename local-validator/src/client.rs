@@ -0,0 +1,35 @@
+use arch_program::{hash::Hash, pubkey::Pubkey};
+
+use crate::bank::ProcessedTransaction;
+use crate::helper::{self, AccountInfoResult};
+
+/// Drop-in for the `ArchRpcClient` calls lesson tests make, answered from
+/// the in-process bank instead of a node.
+#[derive(Clone, Debug, Default)]
+pub struct LocalClient;
+
+impl LocalClient {
+    pub fn new() -> Self {
+        LocalClient
+    }
+
+    pub fn get_best_block_hash(&self) -> Result<Hash, String> {
+        Ok(helper::bank().best_block_hash())
+    }
+
+    pub fn get_block_count(&self) -> Result<u64, String> {
+        Ok(helper::bank().block_height())
+    }
+
+    pub fn get_processed_transaction(
+        &self,
+        txid: &str,
+    ) -> Result<Option<ProcessedTransaction>, String> {
+        Ok(helper::bank().get_processed_transaction(txid).cloned())
+    }
+
+    pub fn read_account_info(&self, pubkey: Pubkey) -> Result<AccountInfoResult, String> {
+        AccountInfoResult::load(&helper::bank(), &pubkey)
+            .ok_or_else(|| format!("Account {} not found", pubkey))
+    }
+}
@@ -0,0 +1,120 @@
+use arch_program::pubkey::Pubkey;
+use arch_sdk::RuntimeTransaction;
+use bitcoin::{key::Keypair, Amount, Network, OutPoint};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::bank::{Bank, ProcessInstruction, ProcessedTransaction};
+use crate::regtest::{self, Regtest};
+
+/// Lamports the local faucet hands out per request.
+pub const FAUCET_LAMPORTS: u64 = 1_000_000_000;
+
+/// Value of the UTXO `send_utxo` anchors an account to, as in arch_test_sdk.
+pub const ACCOUNT_UTXO_SATS: u64 = 3_000;
+
+/// Account state as returned by `read_account_info`.
+#[derive(Clone, Debug)]
+pub struct AccountInfoResult {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub lamports: u64,
+    /// The anchoring UTXO as `txid:vout`, empty if the account has none.
+    pub utxo: String,
+    pub is_executable: bool,
+}
+
+impl AccountInfoResult {
+    pub fn load(bank: &Bank, pubkey: &Pubkey) -> Option<Self> {
+        let account = bank.get_account(pubkey)?;
+        Some(AccountInfoResult {
+            owner: account.owner,
+            data: account.data.clone(),
+            lamports: account.lamports,
+            utxo: bank
+                .account_utxo(pubkey)
+                .map(|utxo| utxo.to_string())
+                .unwrap_or_default(),
+            is_executable: account.is_executable,
+        })
+    }
+}
+
+/// The bank shared by every test in the process. Tests generate fresh
+/// keypairs, so they can share one ledger the way they'd share a node.
+pub fn bank() -> MutexGuard<'static, Bank> {
+    static BANK: OnceLock<Mutex<Bank>> = OnceLock::new();
+    BANK.get_or_init(|| Mutex::new(Bank::new()))
+        .lock()
+        // A failed assertion in one test must not wedge the rest
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The regtest chain shared by every test, standing in for bitcoind.
+pub fn regtest() -> MutexGuard<'static, Regtest> {
+    static REGTEST: OnceLock<Mutex<Regtest>> = OnceLock::new();
+    REGTEST
+        .get_or_init(|| Mutex::new(Regtest::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Makes a program callable by transactions; stands in for deploying it.
+pub fn register_program(program_id: &Pubkey, entrypoint: ProcessInstruction) {
+    bank().add_program(program_id, entrypoint);
+}
+
+pub fn pubkey_from_keypair(keypair: &Keypair) -> Pubkey {
+    Pubkey::from_slice(&keypair.x_only_public_key().0.serialize())
+}
+
+pub fn create_and_fund_account_with_faucet(keypair: &Keypair, _network: Network) {
+    bank().airdrop(&pubkey_from_keypair(keypair), FAUCET_LAMPORTS);
+}
+
+/// Processes the transactions in a single block and returns them once
+/// they're final, which in-process is immediately.
+pub fn send_transactions_and_wait(
+    transactions: Vec<RuntimeTransaction>,
+) -> Vec<ProcessedTransaction> {
+    bank().process_transactions(transactions)
+}
+
+pub fn read_account_info(pubkey: Pubkey) -> AccountInfoResult {
+    match AccountInfoResult::load(&bank(), &pubkey) {
+        Some(account) => account,
+        None => panic!("Account {} not found", pubkey),
+    }
+}
+
+/// Sends a UTXO to the account's regtest address, confirms it and anchors
+/// the account to it. Returns the txid and vout, like arch_test_sdk.
+pub fn send_utxo(pubkey: Pubkey) -> (String, u32) {
+    let address = regtest::account_address(&pubkey).unwrap_or_else(|err| panic!("{}", err));
+    let txid = {
+        let mut regtest = regtest();
+        let txid = regtest
+            .send_to_address(&address, Amount::from_sat(ACCOUNT_UTXO_SATS))
+            .unwrap_or_else(|err| panic!("Failed to send UTXO: {}", err));
+        let miner = regtest.wallet_address().clone();
+        regtest.mine_blocks(1, &miner);
+        txid
+    };
+
+    // The payment is output 0 of wallet transactions
+    let utxo = OutPoint::new(txid, 0);
+    bank().anchor_account(&pubkey, utxo);
+    (txid.to_string(), utxo.vout)
+}
+
+/// Mines blocks to the wallet, confirming anything in the mempool.
+pub fn mine_blocks(count: u64) {
+    let mut regtest = regtest();
+    let miner = regtest.wallet_address().clone();
+    regtest.mine_blocks(count, &miner);
+}
+
+/// Confirmations of a regtest transaction; 0 while it's in the mempool.
+pub fn get_confirmations(txid: &str) -> Option<u64> {
+    let txid = txid.parse().ok()?;
+    regtest().confirmations(&txid)
+}
@@ -0,0 +1,34 @@
+// ============================================================================
+// LOCAL VALIDATOR - An In-Process Stand-In for an Arch Node
+// ============================================================================
+// Lesson tests normally talk to a live node through ArchRpcClient, a faucet
+// and a Bitcoin network. This crate runs the same flow in-process: programs
+// are registered as native functions that can invoke each other and sign
+// for their program addresses with invoke_signed, transactions have their
+// signatures checked and execute against an in-memory accounts map, and
+// the helpers mirror arch_test_sdk so tests only change their imports. A
+// regtest chain stands in for bitcoind, funding accounts with UTXOs and
+// confirming them as blocks are mined. With the `node` feature, the
+// `local-node` binary serves the same bank over the node's JSON-RPC API and
+// the regtest chain over bitcoind's.
+
+pub mod bank;
+#[cfg(feature = "node")]
+pub mod bitcoin_rpc;
+pub mod client;
+pub mod helper;
+pub mod regtest;
+#[cfg(feature = "node")]
+pub mod rpc;
+#[cfg(feature = "simulate")]
+pub mod simulate;
+pub mod system_program;
+
+mod bip322;
+mod runtime;
+mod syscalls;
+
+pub use bank::{
+    Account, Bank, InstructionOutcome, MessageOutcome, ProcessInstruction, ProcessedTransaction,
+};
+pub use client::LocalClient;
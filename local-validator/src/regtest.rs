@@ -0,0 +1,431 @@
+use arch_program::pubkey::Pubkey;
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::hashes::{sha256d, Hash as _};
+use bitcoin::key::{Keypair, XOnlyPublicKey};
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{
+    absolute, transaction, Address, Amount, BlockHash, Network, OutPoint, ScriptBuf, Sequence,
+    Transaction, TxIn, TxOut, Txid, Witness,
+};
+use std::collections::HashMap;
+
+/// Blocks a coinbase output must be buried under before it can be spent.
+pub const COINBASE_MATURITY: u64 = 100;
+
+/// Regtest halves the block reward every 150 blocks.
+const SUBSIDY_HALVING_INTERVAL: u64 = 150;
+
+const INITIAL_SUBSIDY: u64 = 50 * 100_000_000;
+
+/// Flat fee the wallet pays per transaction; there's no fee market to estimate.
+pub const WALLET_FEE: Amount = Amount::from_sat(1_000);
+
+/// An unspent output and where it was confirmed.
+#[derive(Clone, Debug)]
+pub struct Utxo {
+    pub outpoint: OutPoint,
+    pub txout: TxOut,
+    /// `None` while the transaction is in the mempool.
+    pub height: Option<u64>,
+    pub is_coinbase: bool,
+}
+
+struct Block {
+    hash: BlockHash,
+    txids: Vec<Txid>,
+}
+
+/// An in-memory Bitcoin regtest chain with a single built-in wallet, in the
+/// spirit of `bitcoind -regtest`: the wallet is funded by mining, pays
+/// addresses from its UTXOs, and transactions confirm when blocks are mined.
+/// Scripts and signatures aren't validated, since every spend comes from
+/// the wallet itself.
+pub struct Regtest {
+    blocks: Vec<Block>,
+    transactions: HashMap<Txid, Transaction>,
+    confirmed_at: HashMap<Txid, u64>,
+    mempool: Vec<Txid>,
+    utxos: HashMap<OutPoint, Utxo>,
+    wallet: Address,
+}
+
+impl Default for Regtest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Regtest {
+    /// Starts a chain with spendable wallet funds, like the usual
+    /// `generatetoaddress 101` after starting a fresh regtest node.
+    pub fn new() -> Self {
+        let secp = Secp256k1::new();
+        let keypair = Keypair::from_seckey_slice(&secp, &[1; 32]).expect("valid secret key");
+        let wallet = Address::p2tr(&secp, keypair.x_only_public_key().0, None, Network::Regtest);
+
+        let mut regtest = Regtest {
+            blocks: vec![Block {
+                hash: BlockHash::from_byte_array(sha256d::Hash::hash(b"regtest genesis").to_byte_array()),
+                txids: Vec::new(),
+            }],
+            transactions: HashMap::new(),
+            confirmed_at: HashMap::new(),
+            mempool: Vec::new(),
+            utxos: HashMap::new(),
+            wallet,
+        };
+        let wallet = regtest.wallet.clone();
+        regtest.mine_blocks(COINBASE_MATURITY + 1, &wallet);
+        regtest
+    }
+
+    /// Height of the tip; genesis is height 0.
+    pub fn block_count(&self) -> u64 {
+        self.blocks.len() as u64 - 1
+    }
+
+    pub fn best_block_hash(&self) -> BlockHash {
+        self.blocks.last().expect("genesis is never removed").hash
+    }
+
+    pub fn block_hash(&self, height: u64) -> Option<BlockHash> {
+        self.blocks.get(height as usize).map(|block| block.hash)
+    }
+
+    /// A block's height and the txids it confirmed, coinbase first.
+    pub fn get_block(&self, hash: &BlockHash) -> Option<(u64, &[Txid])> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .find(|(_, block)| block.hash == *hash)
+            .map(|(height, block)| (height as u64, block.txids.as_slice()))
+    }
+
+    pub fn wallet_address(&self) -> &Address {
+        &self.wallet
+    }
+
+    /// What the wallet can spend now: mature coinbases and its own
+    /// payments and change, confirmed or not.
+    pub fn balance(&self) -> Amount {
+        self.spendable_wallet_utxos()
+            .iter()
+            .map(|utxo| utxo.txout.value)
+            .sum()
+    }
+
+    pub fn get_transaction(&self, txid: &Txid) -> Option<&Transaction> {
+        self.transactions.get(txid)
+    }
+
+    pub fn mempool(&self) -> &[Txid] {
+        &self.mempool
+    }
+
+    /// 0 while in the mempool, 1 once mined, growing with each block after.
+    pub fn confirmations(&self, txid: &Txid) -> Option<u64> {
+        if !self.transactions.contains_key(txid) {
+            return None;
+        }
+        Some(self.confirmations_at(self.confirmed_at.get(txid).copied()))
+    }
+
+    pub fn get_tx_out(&self, outpoint: &OutPoint) -> Option<&Utxo> {
+        self.utxos.get(outpoint)
+    }
+
+    pub fn confirmations_of(&self, utxo: &Utxo) -> u64 {
+        self.confirmations_at(utxo.height)
+    }
+
+    pub fn list_unspent(&self, address: &Address) -> Vec<&Utxo> {
+        let script = address.script_pubkey();
+        let mut utxos: Vec<&Utxo> = self
+            .utxos
+            .values()
+            .filter(|utxo| utxo.txout.script_pubkey == script)
+            .collect();
+        utxos.sort_by_key(|utxo| (utxo.height, utxo.outpoint));
+        utxos
+    }
+
+    /// Pays `amount` to `address` from the wallet and returns the txid; the
+    /// payment is always output 0 and change, if any, output 1. The
+    /// transaction waits in the mempool until a block is mined.
+    pub fn send_to_address(&mut self, address: &Address, amount: Amount) -> Result<Txid, String> {
+        let needed = amount
+            .checked_add(WALLET_FEE)
+            .ok_or("Amount out of range")?;
+
+        let mut available = self.spendable_wallet_utxos();
+        available.sort_by_key(|utxo| std::cmp::Reverse(utxo.txout.value));
+        let mut inputs = Vec::new();
+        let mut total = Amount::ZERO;
+        for utxo in available {
+            if total >= needed {
+                break;
+            }
+            total += utxo.txout.value;
+            inputs.push(utxo.outpoint);
+        }
+        if total < needed {
+            return Err(format!(
+                "Insufficient funds: need {} including fee, wallet has {}",
+                needed,
+                total
+            ));
+        }
+
+        let mut output = vec![TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        }];
+        let change = total - needed;
+        if change > Amount::ZERO {
+            output.push(TxOut {
+                value: change,
+                script_pubkey: self.wallet.script_pubkey(),
+            });
+        }
+
+        let transaction = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: inputs
+                .into_iter()
+                .map(|previous_output| TxIn {
+                    previous_output,
+                    script_sig: ScriptBuf::new(),
+                    sequence: Sequence::MAX,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        };
+        Ok(self.add_transaction(transaction, false))
+    }
+
+    /// Mines `count` blocks paying the reward to `address`, confirming the
+    /// mempool in the first one.
+    pub fn mine_blocks(&mut self, count: u64, address: &Address) -> Vec<BlockHash> {
+        (0..count).map(|_| self.mine_block(address)).collect()
+    }
+
+    fn mine_block(&mut self, address: &Address) -> BlockHash {
+        let height = self.block_count() + 1;
+        let halvings = height / SUBSIDY_HALVING_INTERVAL;
+        let subsidy = if halvings >= 64 { 0 } else { INITIAL_SUBSIDY >> halvings };
+
+        // The height in the coinbase script keeps coinbase txids unique (BIP 34)
+        let coinbase = Transaction {
+            version: transaction::Version::TWO,
+            lock_time: absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::from_bytes(height.to_le_bytes().to_vec()),
+                sequence: Sequence::MAX,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::from_sat(subsidy),
+                script_pubkey: address.script_pubkey(),
+            }],
+        };
+        let mut txids = vec![self.add_transaction(coinbase, true)];
+        txids.append(&mut self.mempool);
+
+        let mut header = self.best_block_hash().to_byte_array().to_vec();
+        for txid in &txids {
+            header.extend_from_slice(&txid.to_byte_array());
+            self.confirmed_at.insert(*txid, height);
+            let outputs = self.transactions[txid].output.len() as u32;
+            for vout in 0..outputs {
+                if let Some(utxo) = self.utxos.get_mut(&OutPoint::new(*txid, vout)) {
+                    utxo.height = Some(height);
+                }
+            }
+        }
+        let hash = BlockHash::from_byte_array(sha256d::Hash::hash(&header).to_byte_array());
+        self.blocks.push(Block { hash, txids });
+        hash
+    }
+
+    /// Spends the transaction's inputs and adds its outputs as unconfirmed.
+    /// Coinbases go straight into the block being mined; anything else
+    /// waits in the mempool.
+    fn add_transaction(&mut self, transaction: Transaction, is_coinbase: bool) -> Txid {
+        let txid = transaction.compute_txid();
+        if !is_coinbase {
+            for input in &transaction.input {
+                self.utxos.remove(&input.previous_output);
+            }
+            self.mempool.push(txid);
+        }
+        for (vout, txout) in transaction.output.iter().enumerate() {
+            let outpoint = OutPoint::new(txid, vout as u32);
+            self.utxos.insert(
+                outpoint,
+                Utxo {
+                    outpoint,
+                    txout: txout.clone(),
+                    height: None,
+                    is_coinbase,
+                },
+            );
+        }
+        self.transactions.insert(txid, transaction);
+        txid
+    }
+
+    fn spendable_wallet_utxos(&self) -> Vec<Utxo> {
+        let script = self.wallet.script_pubkey();
+        self.utxos
+            .values()
+            .filter(|utxo| utxo.txout.script_pubkey == script)
+            // Every other transaction comes from this wallet, so like bitcoind
+            // it trusts its own change before it confirms. Buried under
+            // COINBASE_MATURITY blocks means one more confirmation
+            .filter(|utxo| {
+                !utxo.is_coinbase || self.confirmations_at(utxo.height) > COINBASE_MATURITY
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn confirmations_at(&self, height: Option<u64>) -> u64 {
+        height.map_or(0, |height| self.block_count() - height + 1)
+    }
+}
+
+/// The regtest address an Arch account's UTXO is sent to: a taproot output
+/// for the account's key. Program-derived addresses aren't valid keys, so
+/// they have no address of their own.
+pub fn account_address(pubkey: &Pubkey) -> Result<Address, String> {
+    let key = XOnlyPublicKey::from_slice(&pubkey.serialize())
+        .map_err(|e| format!("Account {} has no Bitcoin address: {}", pubkey, e))?;
+    Ok(Address::p2tr(&Secp256k1::verification_only(), key, None, Network::Regtest))
+}
+
+pub fn parse_address(address: &str) -> Result<Address, String> {
+    address
+        .parse::<Address<NetworkUnchecked>>()
+        .map_err(|e| format!("Invalid address {}: {}", address, e))?
+        .require_network(Network::Regtest)
+        .map_err(|e| format!("Invalid address {}: {}", address, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> XOnlyPublicKey {
+        let keypair = Keypair::from_seckey_slice(&Secp256k1::new(), &[seed; 32]).unwrap();
+        keypair.x_only_public_key().0
+    }
+
+    fn address(seed: u8) -> Address {
+        Address::p2tr(&Secp256k1::new(), key(seed), None, Network::Regtest)
+    }
+
+    #[test]
+    fn new_chains_have_one_mature_coinbase() {
+        let regtest = Regtest::new();
+
+        assert_eq!(regtest.block_count(), COINBASE_MATURITY + 1);
+        assert_eq!(regtest.balance(), Amount::from_sat(INITIAL_SUBSIDY));
+        assert_eq!(regtest.list_unspent(regtest.wallet_address()).len(), COINBASE_MATURITY as usize + 1);
+    }
+
+    #[test]
+    fn payments_confirm_when_mined() {
+        let mut regtest = Regtest::new();
+        let recipient = address(2);
+        let miner = address(3);
+
+        let txid = regtest.send_to_address(&recipient, Amount::from_sat(3_000)).unwrap();
+        assert_eq!(regtest.mempool(), [txid]);
+        assert_eq!(regtest.confirmations(&txid), Some(0));
+        assert_eq!(regtest.list_unspent(&recipient)[0].height, None);
+
+        let blocks = regtest.mine_blocks(3, &miner);
+        assert!(regtest.mempool().is_empty());
+        assert_eq!(regtest.confirmations(&txid), Some(3));
+        let (height, txids) = regtest.get_block(&blocks[0]).unwrap();
+        assert_eq!(height, COINBASE_MATURITY + 2);
+        assert_eq!(txids[1], txid);
+
+        let payment = regtest.get_tx_out(&OutPoint::new(txid, 0)).unwrap();
+        assert_eq!(payment.txout.value, Amount::from_sat(3_000));
+        assert_eq!(regtest.confirmations_of(payment), 3);
+        let change = regtest.get_tx_out(&OutPoint::new(txid, 1)).unwrap();
+        assert_eq!(change.txout.value, Amount::from_sat(INITIAL_SUBSIDY - 3_000) - WALLET_FEE);
+    }
+
+    #[test]
+    fn spent_outputs_are_gone() {
+        let mut regtest = Regtest::new();
+        let spent = regtest
+            .list_unspent(regtest.wallet_address())
+            .into_iter()
+            .find(|utxo| utxo.height == Some(1))
+            .unwrap()
+            .outpoint;
+
+        let txid = regtest.send_to_address(&address(2), Amount::from_sat(3_000)).unwrap();
+
+        assert_eq!(regtest.get_transaction(&txid).unwrap().input[0].previous_output, spent);
+        assert!(regtest.get_tx_out(&spent).is_none());
+    }
+
+    #[test]
+    fn only_mature_coinbases_and_change_are_spendable() {
+        let mut regtest = Regtest::new();
+        let payment = Amount::from_sat(3_000);
+        let balance = regtest.balance();
+
+        // Change can be spent again before a block confirms it
+        let first = regtest.send_to_address(&address(2), payment).unwrap();
+        assert_eq!(regtest.balance(), balance - payment - WALLET_FEE);
+        let second = regtest.send_to_address(&address(2), payment).unwrap();
+        assert_eq!(
+            regtest.get_transaction(&second).unwrap().input[0].previous_output,
+            OutPoint::new(first, 1)
+        );
+        let balance = balance - (payment + WALLET_FEE) * 2;
+        assert_eq!(regtest.balance(), balance);
+
+        // Mining matures the coinbase from height 2, but not the fresh one
+        let wallet = regtest.wallet_address().clone();
+        regtest.mine_blocks(1, &wallet);
+        let balance = balance + Amount::from_sat(INITIAL_SUBSIDY);
+        assert_eq!(regtest.balance(), balance);
+        let err = regtest.send_to_address(&address(2), balance).unwrap_err();
+        assert!(err.contains("Insufficient funds"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_transactions_have_no_confirmations() {
+        let regtest = Regtest::new();
+        assert_eq!(regtest.confirmations(&Txid::all_zeros()), None);
+        assert_eq!(regtest.block_hash(regtest.block_count() + 1), None);
+    }
+
+    #[test]
+    fn accounts_have_addresses_only_for_valid_keys() {
+        let pubkey = Pubkey::from_slice(&key(2).serialize());
+        assert_eq!(account_address(&pubkey).unwrap(), address(2));
+
+        // Not the x coordinate of any curve point, like a program address
+        assert!(account_address(&Pubkey::from_slice(&[0xff; 32])).is_err());
+    }
+
+    #[test]
+    fn only_regtest_addresses_parse() {
+        let regtest_address = address(2).to_string();
+        assert_eq!(parse_address(&regtest_address).unwrap(), address(2));
+
+        let mainnet = Address::p2tr(&Secp256k1::new(), key(2), None, Network::Bitcoin);
+        assert!(parse_address(&mainnet.to_string()).is_err());
+        assert!(parse_address("not an address").is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use arch_program::pubkey::Pubkey;
+use arch_sdk::RuntimeTransaction;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::bank::{self, ProcessedTransaction};
+use crate::helper::{self, AccountInfoResult, FAUCET_LAMPORTS};
+use crate::regtest;
+
+/// JSON-RPC 2.0 request as sent by `ArchRpcClient`.
+#[derive(Deserialize)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Serialize)]
+pub struct RpcResponse {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn method_not_found(method: &str) -> Self {
+        RpcError {
+            code: -32601,
+            message: format!("Method not found: {}", method),
+        }
+    }
+
+    fn invalid_params(message: String) -> Self {
+        RpcError {
+            code: -32602,
+            message,
+        }
+    }
+
+    fn not_found(message: String) -> Self {
+        RpcError {
+            code: 404,
+            message,
+        }
+    }
+}
+
+pub fn handle(request: RpcRequest) -> RpcResponse {
+    respond(request.id, dispatch(&request.method, request.params))
+}
+
+pub fn respond(id: Value, result: Result<Value, RpcError>) -> RpcResponse {
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    RpcResponse {
+        jsonrpc: "2.0",
+        id,
+        result,
+        error,
+    }
+}
+
+/// The subset of the Arch node API that `arch_sdk` clients and lesson tests
+/// use, answered from the shared local bank.
+pub fn dispatch(method: &str, params: Value) -> Result<Value, RpcError> {
+    match method {
+        "is_node_ready" => Ok(json!(true)),
+        "get_block_count" => Ok(json!(helper::bank().block_height())),
+        "get_best_block_hash" => Ok(json!(helper::bank().best_block_hash_hex())),
+        "read_account_info" => {
+            let pubkey = parse_pubkey(&params)?;
+            let account = AccountInfoResult::load(&helper::bank(), &pubkey)
+                .ok_or_else(|| RpcError::not_found(format!("Account {} not found", pubkey)))?;
+            Ok(json!({
+                "owner": account.owner,
+                "data": account.data,
+                "lamports": account.lamports,
+                "utxo": account.utxo,
+                "is_executable": account.is_executable,
+            }))
+        }
+        "get_account_address" => {
+            let pubkey = parse_pubkey(&params)?;
+            let address = regtest::account_address(&pubkey).map_err(RpcError::invalid_params)?;
+            Ok(json!(address.to_string()))
+        }
+        "send_transaction" => {
+            let transaction: RuntimeTransaction = parse_params(params)?;
+            sanitize_transactions(std::slice::from_ref(&transaction))?;
+            let processed = helper::send_transactions_and_wait(vec![transaction]);
+            Ok(json!(processed[0].txid))
+        }
+        "send_transactions" => {
+            let transactions: Vec<RuntimeTransaction> = parse_params(params)?;
+            sanitize_transactions(&transactions)?;
+            let processed = helper::send_transactions_and_wait(transactions);
+            let txids: Vec<_> = processed.into_iter().map(|tx| tx.txid).collect();
+            Ok(json!(txids))
+        }
+        "get_processed_transaction" => {
+            let txid: String = parse_params(params)?;
+            let bank = helper::bank();
+            Ok(bank
+                .get_processed_transaction(&txid)
+                .map(processed_transaction_json)
+                .unwrap_or(Value::Null))
+        }
+        // The faucet funds directly instead of through a Bitcoin transaction
+        "create_account_with_faucet" | "request_airdrop" => {
+            let pubkey = parse_pubkey(&params)?;
+            helper::bank().airdrop(&pubkey, FAUCET_LAMPORTS);
+            Ok(json!(true))
+        }
+        _ => Err(RpcError::method_not_found(method)),
+    }
+}
+
+fn parse_params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::invalid_params(format!("Invalid params: {}", e)))
+}
+
+/// Rejects the whole request if any message is malformed, as the node does,
+/// rather than recording a failed transaction for it.
+fn sanitize_transactions(transactions: &[RuntimeTransaction]) -> Result<(), RpcError> {
+    for (index, transaction) in transactions.iter().enumerate() {
+        bank::sanitize_message(&transaction.message).map_err(|err| {
+            RpcError::invalid_params(format!("Invalid transaction {}: {}", index, err))
+        })?;
+    }
+    Ok(())
+}
+
+/// Accepts a pubkey as its 32 serialized bytes, which is what
+/// `ArchRpcClient` sends, or as a hex string for use with curl.
+fn parse_pubkey(params: &Value) -> Result<Pubkey, RpcError> {
+    let bytes = match params {
+        Value::String(hex_key) => hex::decode(hex_key)
+            .map_err(|e| RpcError::invalid_params(format!("Invalid pubkey: {}", e)))?,
+        params => parse_params::<Vec<u8>>(params.clone())?,
+    };
+    if bytes.len() != 32 {
+        return Err(RpcError::invalid_params(format!(
+            "Pubkey must be 32 bytes, found {}",
+            bytes.len()
+        )));
+    }
+    Ok(Pubkey::from_slice(&bytes))
+}
+
+fn processed_transaction_json(transaction: &ProcessedTransaction) -> Value {
+    json!({
+        "runtime_transaction": transaction.runtime_transaction,
+        "status": transaction.status,
+        "bitcoin_txid": null,
+        "logs": transaction.logs,
+        "rollback_status": "NotRolledback",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_program::{sanitized::ArchMessage, system_instruction};
+    use arch_sdk::{build_and_sign_transaction, generate_new_keypair};
+    use bitcoin::Network;
+
+    fn signed_transfer() -> RuntimeTransaction {
+        let (payer, payer_pubkey, _) = generate_new_keypair(Network::Regtest);
+        let (_, recipient, _) = generate_new_keypair(Network::Regtest);
+        helper::bank().airdrop(&payer_pubkey, 1_000);
+        let message = ArchMessage::new(
+            &[system_instruction::transfer(&payer_pubkey, &recipient, 400)],
+            Some(payer_pubkey),
+            helper::bank().best_block_hash(),
+        );
+        build_and_sign_transaction(message, vec![payer], Network::Regtest)
+    }
+
+    #[test]
+    fn send_transaction_returns_txid() {
+        let txid = dispatch("send_transaction", json!(signed_transfer())).unwrap();
+        let processed = dispatch("get_processed_transaction", txid).unwrap();
+        assert_eq!(processed["status"], json!("Processed"));
+    }
+
+    #[test]
+    fn malformed_transactions_are_invalid_params() {
+        let mut transaction = signed_transfer();
+        transaction.message.instructions[0].program_id_index = 9;
+
+        let err = dispatch("send_transaction", json!(transaction)).unwrap_err();
+        assert_eq!(err.code, -32602);
+
+        let err = dispatch("send_transactions", json!([signed_transfer(), transaction])).unwrap_err();
+        assert_eq!(err.code, -32602);
+        assert!(err.message.starts_with("Invalid transaction 1"));
+    }
+
+    #[test]
+    fn unknown_methods_are_not_found() {
+        assert_eq!(dispatch("get_everything", Value::Null).unwrap_err().code, -32601);
+    }
+}
@@ -0,0 +1,544 @@
+use arch_program::{
+    account::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+    program_error::ProgramError, pubkey::Pubkey,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::bank::ProcessInstruction;
+use crate::syscalls::push_log;
+
+/// Deepest call stack allowed, counting the top-level instruction as 1.
+pub const MAX_INVOKE_DEPTH: usize = 5;
+
+thread_local! {
+    static PROGRAMS: RefCell<HashMap<Pubkey, ProcessInstruction>> = RefCell::new(HashMap::new());
+    static CALL_STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+    /// A failed cross-program invocation fails the whole instruction, even
+    /// if the caller ignores the error it gets back.
+    static CPI_FAILURE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// A program on the call stack and the account state its changes are
+/// checked against.
+struct Frame {
+    program_id: Pubkey,
+    before: Vec<AccountSnapshot>,
+    lamports: u128,
+}
+
+/// Makes `programs` invokable, by the bank and by `invoke`, while `f` runs.
+pub fn with_programs<R>(programs: &HashMap<Pubkey, ProcessInstruction>, f: impl FnOnce() -> R) -> R {
+    PROGRAMS.with(|registry| *registry.borrow_mut() = programs.clone());
+    let result = f();
+    PROGRAMS.with(|registry| registry.borrow_mut().clear());
+    result
+}
+
+/// Runs a program and enforces the runtime's account rules on the result.
+/// Used for top-level instructions and for each nested invocation.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> Result<(), String> {
+    let entrypoint = PROGRAMS
+        .with(|registry| registry.borrow().get(program_id).copied())
+        .ok_or_else(|| format!("Program {} is not deployed", program_id))?;
+
+    let depth = CALL_STACK.with(|stack| stack.borrow().len()) + 1;
+    if depth == 1 {
+        CPI_FAILURE.with(|failure| failure.borrow_mut().take());
+    }
+    push_log(format!("Program {} invoke [{}]", program_id, depth));
+
+    let before = snapshot(accounts);
+    let lamports = total_lamports(&before);
+    CALL_STACK.with(|stack| {
+        stack.borrow_mut().push(Frame {
+            program_id: *program_id,
+            before,
+            lamports,
+        })
+    });
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        entrypoint(program_id, accounts, data)
+    }));
+    let frame = CALL_STACK
+        .with(|stack| stack.borrow_mut().pop())
+        .expect("call stack holds the running program");
+
+    let result = match result {
+        Ok(Ok(())) => {
+            let after = snapshot(accounts);
+            verify_account_changes(program_id, &frame.before, &after).and_then(|()| {
+                if total_lamports(&after) == frame.lamports {
+                    Ok(())
+                } else {
+                    Err("Sum of account lamports changed".to_string())
+                }
+            })
+        }
+        Ok(Err(err)) => Err(program_error_message(&err)),
+        Err(_) => Err("Program panicked".to_string()),
+    };
+    let result = match (result, CPI_FAILURE.with(|failure| failure.borrow().clone())) {
+        (Ok(()), Some(failure)) if depth == 1 => Err(failure),
+        (result, _) => result,
+    };
+
+    match &result {
+        Ok(()) => push_log(format!("Program {} success", program_id)),
+        Err(err) => push_log(format!("Program {} failed: {}", program_id, err)),
+    }
+    result
+}
+
+/// Backs `invoke` and `invoke_signed`: checks the callee only gets privileges
+/// the caller was given, settles the caller's changes so far, then runs the
+/// callee against the same account storage.
+pub fn invoke_signed(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    signers_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let Some(caller) = CALL_STACK.with(|stack| stack.borrow().last().map(|frame| frame.program_id))
+    else {
+        push_log("invoke called outside of a program".to_string());
+        return Err(ProgramError::InvalidArgument);
+    };
+    let depth = CALL_STACK.with(|stack| stack.borrow().len());
+    if depth >= MAX_INVOKE_DEPTH {
+        return fail_invoke(
+            format!("Cross-program invocation exceeds max depth of {}", MAX_INVOKE_DEPTH),
+            ProgramError::InvalidArgument,
+        );
+    }
+    // A program signs for the addresses it derives from its own id
+    let mut pda_signers = Vec::with_capacity(signers_seeds.len());
+    for seeds in signers_seeds {
+        match Pubkey::create_program_address(seeds, &caller) {
+            Ok(address) => pda_signers.push(address),
+            Err(err) => {
+                return fail_invoke(
+                    format!("Could not derive a program address from signer seeds: {:?}", err),
+                    ProgramError::InvalidSeeds,
+                )
+            }
+        }
+    }
+
+    let mut callee_accounts = Vec::with_capacity(instruction.accounts.len());
+    for meta in &instruction.accounts {
+        let Some(account) = account_infos.iter().find(|account| *account.key == meta.pubkey) else {
+            return fail_invoke(
+                format!("Instruction references account {} that wasn't passed to invoke", meta.pubkey),
+                ProgramError::NotEnoughAccountKeys,
+            );
+        };
+        if meta.is_writable && !account.is_writable {
+            return fail_invoke(
+                format!("{}'s writable privilege escalated", meta.pubkey),
+                ProgramError::InvalidArgument,
+            );
+        }
+        if meta.is_signer && !account.is_signer && !pda_signers.contains(&meta.pubkey) {
+            return fail_invoke(
+                format!("{}'s signer privilege escalated", meta.pubkey),
+                ProgramError::MissingRequiredSignature,
+            );
+        }
+
+        let mut callee_account = account.clone();
+        callee_account.is_signer = meta.is_signer;
+        callee_account.is_writable = meta.is_writable;
+        callee_accounts.push(callee_account);
+    }
+
+    // Changes the caller made before the call must already be legal, and
+    // from here on it's only accountable for what it does after the call
+    let pre_invoke = snapshot(account_infos);
+    let settled = CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let frame = stack.last_mut().expect("caller is on the call stack");
+        verify_account_changes(&caller, &frame.before, &pre_invoke)
+    });
+    if let Err(err) = settled {
+        return fail_invoke(err, ProgramError::InvalidAccountData);
+    }
+
+    if let Err(err) = process_instruction(&instruction.program_id, &callee_accounts, &instruction.data) {
+        return fail_invoke(
+            format!("Cross-program invocation of {} failed: {}", instruction.program_id, err),
+            ProgramError::InvalidArgument,
+        );
+    }
+
+    let post_invoke = snapshot(account_infos);
+    CALL_STACK.with(|stack| {
+        let mut stack = stack.borrow_mut();
+        let frame = stack.last_mut().expect("caller is on the call stack");
+        for before in &mut frame.before {
+            if let Some(after) = post_invoke.iter().find(|after| after.key == before.key) {
+                before.lamports = after.lamports;
+                before.data = after.data.clone();
+                before.owner = after.owner;
+            }
+        }
+    });
+    Ok(())
+}
+
+fn fail_invoke(message: String, error: ProgramError) -> ProgramResult {
+    push_log(message.clone());
+    CPI_FAILURE.with(|failure| {
+        failure.borrow_mut().get_or_insert(message);
+    });
+    Err(error)
+}
+
+pub fn program_error_message(err: &ProgramError) -> String {
+    match err {
+        ProgramError::Custom(code) => format!("custom program error: {:#x}", code),
+        err => format!("{:?}", err),
+    }
+}
+
+struct AccountSnapshot {
+    key: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    owner: Pubkey,
+    is_writable: bool,
+}
+
+fn snapshot(accounts: &[AccountInfo]) -> Vec<AccountSnapshot> {
+    let mut snapshots: Vec<AccountSnapshot> = Vec::new();
+    for account in accounts {
+        // Duplicated accounts share storage, so one snapshot covers them
+        if snapshots.iter().any(|snapshot| snapshot.key == *account.key) {
+            continue;
+        }
+        snapshots.push(AccountSnapshot {
+            key: *account.key,
+            lamports: account.lamports(),
+            data: account.data.borrow().to_vec(),
+            owner: *account.owner,
+            is_writable: account.is_writable,
+        });
+    }
+    snapshots
+}
+
+fn total_lamports(snapshots: &[AccountSnapshot]) -> u128 {
+    snapshots.iter().map(|snapshot| snapshot.lamports as u128).sum()
+}
+
+/// The rules the on-chain runtime enforces on a program's changes: only
+/// writable accounts change, and only the owning program edits data,
+/// assigns the account or debits lamports. Accounts are matched by key, so
+/// `after` may cover a subset of `before`.
+fn verify_account_changes(
+    program_id: &Pubkey,
+    before: &[AccountSnapshot],
+    after: &[AccountSnapshot],
+) -> Result<(), String> {
+    for pre in before {
+        let Some(post) = after.iter().find(|post| post.key == pre.key) else {
+            continue;
+        };
+
+        let data_changed = pre.data != post.data;
+        let owner_changed = pre.owner != post.owner;
+        let lamports_changed = pre.lamports != post.lamports;
+
+        if !pre.is_writable && (data_changed || owner_changed || lamports_changed) {
+            return Err(format!("Instruction modified read-only account {}", pre.key));
+        }
+        if pre.owner != *program_id {
+            if data_changed {
+                return Err(format!("Instruction modified data of unowned account {}", pre.key));
+            }
+            if owner_changed {
+                return Err(format!("Instruction reassigned unowned account {}", pre.key));
+            }
+            if post.lamports < pre.lamports {
+                return Err(format!("Instruction spent lamports of unowned account {}", pre.key));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bank::{Bank, MessageOutcome};
+    use arch_program::{instruction::AccountMeta, program, sanitized::ArchMessage};
+
+    fn caller_id() -> Pubkey {
+        Pubkey::from_slice(&[1; 32])
+    }
+
+    fn callee_id() -> Pubkey {
+        Pubkey::from_slice(&[2; 32])
+    }
+
+    /// Asks the callee to treat its first account as a signer, signing with
+    /// the seed in `data[1..]` and the bump in `data[0]`.
+    fn signing_caller(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let (bump, seed) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+        let instruction = Instruction {
+            program_id: callee_id(),
+            accounts: vec![AccountMeta::new_readonly(*accounts[0].key, true)],
+            data: Vec::new(),
+        };
+        program::invoke_signed(&instruction, accounts, &[&[seed, &[*bump]]])
+    }
+
+    fn requires_signer(_program_id: &Pubkey, accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+        if accounts[0].is_signer {
+            Ok(())
+        } else {
+            Err(ProgramError::MissingRequiredSignature)
+        }
+    }
+
+    fn noop_id() -> Pubkey {
+        Pubkey::from_slice(&[3; 32])
+    }
+
+    fn escalating_caller_id() -> Pubkey {
+        Pubkey::from_slice(&[4; 32])
+    }
+
+    fn recursive_id() -> Pubkey {
+        Pubkey::from_slice(&[5; 32])
+    }
+
+    fn noop(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+        Ok(())
+    }
+
+    /// Passes its first account on with more privileges than it was given:
+    /// as a signer when `data[0]` is 0, as writable otherwise. Ignores the
+    /// error `invoke` returns when `data[1]` is 1.
+    fn escalating_caller(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        let key = *accounts[0].key;
+        let meta = if data[0] == 0 {
+            AccountMeta::new_readonly(key, true)
+        } else {
+            AccountMeta::new(key, false)
+        };
+        let instruction = Instruction {
+            program_id: noop_id(),
+            accounts: vec![meta],
+            data: Vec::new(),
+        };
+        let result = program::invoke(&instruction, accounts);
+        if data[1] == 1 {
+            Ok(())
+        } else {
+            result
+        }
+    }
+
+    /// Invokes itself `data[0]` more times.
+    fn recursive(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        match data[0] {
+            0 => Ok(()),
+            remaining => {
+                let instruction = Instruction {
+                    program_id: *program_id,
+                    accounts: Vec::new(),
+                    data: vec![remaining - 1],
+                };
+                program::invoke(&instruction, accounts)
+            }
+        }
+    }
+
+    fn run(bank: &Bank, instruction: Instruction) -> (MessageOutcome, Vec<String>) {
+        let payer = Pubkey::from_slice(&[9; 32]);
+        let message = ArchMessage::new(&[instruction], Some(payer), bank.best_block_hash());
+        let mut logs = Vec::new();
+        let outcome = bank.simulate_message(&message, &mut logs).unwrap();
+        (outcome, logs)
+    }
+
+    fn pda_bank() -> Bank {
+        let mut bank = Bank::new();
+        bank.add_program(&caller_id(), signing_caller);
+        bank.add_program(&callee_id(), requires_signer);
+        bank
+    }
+
+    fn sign_for(bank: &Bank, pda: Pubkey, seed: &[u8], bump: u8) -> (MessageOutcome, Vec<String>) {
+        let mut data = vec![bump];
+        data.extend_from_slice(seed);
+        let instruction = Instruction {
+            program_id: caller_id(),
+            accounts: vec![AccountMeta::new_readonly(pda, false)],
+            data,
+        };
+        run(bank, instruction)
+    }
+
+    #[test]
+    fn program_signs_for_its_derived_address() {
+        let bank = pda_bank();
+        let (pda, bump) = Pubkey::find_program_address(&[b"vault"], &caller_id());
+
+        let (outcome, logs) = sign_for(&bank, pda, b"vault", bump);
+
+        assert_eq!(outcome.error(), None);
+        assert!(logs.contains(&format!("Program {} invoke [2]", callee_id())));
+    }
+
+    #[test]
+    fn seeds_for_another_address_do_not_sign() {
+        let bank = pda_bank();
+        let (pda, _) = Pubkey::find_program_address(&[b"vault"], &caller_id());
+        let (_, other_bump) = Pubkey::find_program_address(&[b"other"], &caller_id());
+
+        let (outcome, logs) = sign_for(&bank, pda, b"other", other_bump);
+
+        assert_eq!(outcome.error(), Some((0, "MissingRequiredSignature")));
+        assert!(logs.iter().any(|log| log.contains("signer privilege escalated")));
+    }
+
+    #[test]
+    fn seeds_without_a_program_address_are_rejected() {
+        let bank = pda_bank();
+        let (pda, _) = Pubkey::find_program_address(&[b"vault"], &caller_id());
+        // Some bump always lands on the curve, where no program address exists
+        let on_curve_bump = (0..=u8::MAX)
+            .find(|bump| Pubkey::create_program_address(&[b"vault", &[*bump]], &caller_id()).is_err())
+            .unwrap();
+
+        let (outcome, logs) = sign_for(&bank, pda, b"vault", on_curve_bump);
+
+        assert_eq!(outcome.error(), Some((0, "InvalidSeeds")));
+        assert!(logs.iter().any(|log| log.contains("Could not derive a program address")));
+    }
+
+    /// The backend's PDA explorer derives addresses without linking
+    /// `arch_program`; both check the same vectors so they stay in step.
+    #[test]
+    fn program_addresses_match_shared_vectors() {
+        let vectors: serde_json::Value = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../backend/testdata/pda_vectors.json"
+        )))
+        .unwrap();
+
+        for vector in vectors.as_array().unwrap() {
+            let decode = |value: &serde_json::Value| hex::decode(value.as_str().unwrap()).unwrap();
+            let program_id = Pubkey::from_slice(&decode(&vector["program_id"]));
+            let seeds: Vec<Vec<u8>> = vector["seeds"].as_array().unwrap().iter().map(decode).collect();
+            let seeds: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+
+            let (address, bump) = Pubkey::find_program_address(&seeds, &program_id);
+            assert_eq!(address.serialize().to_vec(), decode(&vector["address"]));
+            assert_eq!(u64::from(bump), vector["bump"].as_u64().unwrap());
+        }
+    }
+
+    fn cpi_bank() -> Bank {
+        let mut bank = Bank::new();
+        bank.add_program(&noop_id(), noop);
+        bank.add_program(&escalating_caller_id(), escalating_caller);
+        bank.add_program(&recursive_id(), recursive);
+        bank
+    }
+
+    fn escalate(bank: &Bank, writable: bool, ignore_error: bool) -> (MessageOutcome, Vec<String>) {
+        let instruction = Instruction {
+            program_id: escalating_caller_id(),
+            accounts: vec![AccountMeta::new_readonly(Pubkey::from_slice(&[6; 32]), false)],
+            data: vec![u8::from(writable), u8::from(ignore_error)],
+        };
+        run(bank, instruction)
+    }
+
+    fn recurse(bank: &Bank, times: u8) -> (MessageOutcome, Vec<String>) {
+        let instruction = Instruction {
+            program_id: recursive_id(),
+            accounts: Vec::new(),
+            data: vec![times],
+        };
+        run(bank, instruction)
+    }
+
+    #[test]
+    fn callee_cannot_gain_signer_privilege() {
+        let (outcome, logs) = escalate(&cpi_bank(), false, false);
+
+        assert_eq!(outcome.error(), Some((0, "MissingRequiredSignature")));
+        assert!(logs.iter().any(|log| log.contains("signer privilege escalated")));
+        assert!(!logs.contains(&format!("Program {} invoke [2]", noop_id())));
+    }
+
+    #[test]
+    fn callee_cannot_gain_writable_privilege() {
+        let (outcome, logs) = escalate(&cpi_bank(), true, false);
+
+        assert_eq!(outcome.error(), Some((0, "InvalidArgument")));
+        assert!(logs.iter().any(|log| log.contains("writable privilege escalated")));
+        assert!(!logs.contains(&format!("Program {} invoke [2]", noop_id())));
+    }
+
+    #[test]
+    fn failed_invoke_fails_the_instruction_even_if_ignored() {
+        let (outcome, logs) = escalate(&cpi_bank(), false, true);
+
+        let (index, err) = outcome.error().unwrap();
+        assert_eq!(index, 0);
+        assert!(err.contains("signer privilege escalated"), "{}", err);
+        assert_eq!(
+            logs.last().unwrap(),
+            &format!("Program {} failed: {}", escalating_caller_id(), err)
+        );
+
+        // The next instruction starts with a clean slate
+        let (outcome, _) = recurse(&cpi_bank(), 1);
+        assert_eq!(outcome.error(), None);
+    }
+
+    #[test]
+    fn nested_invocations_are_logged_with_their_depth() {
+        let (outcome, logs) = recurse(&cpi_bank(), 1);
+
+        assert_eq!(outcome.error(), None);
+        let program = recursive_id();
+        assert_eq!(
+            logs,
+            [
+                format!("Program {} invoke [1]", program),
+                format!("Program {} invoke [2]", program),
+                format!("Program {} success", program),
+                format!("Program {} success", program),
+            ]
+        );
+    }
+
+    #[test]
+    fn invocations_stop_at_the_max_depth() {
+        let bank = cpi_bank();
+        let deepest = MAX_INVOKE_DEPTH as u8 - 1;
+
+        let (outcome, logs) = recurse(&bank, deepest);
+        assert_eq!(outcome.error(), None);
+        assert!(logs.contains(&format!("Program {} invoke [{}]", recursive_id(), MAX_INVOKE_DEPTH)));
+
+        let (outcome, logs) = recurse(&bank, deepest + 1);
+        assert!(outcome.error().is_some());
+        assert!(logs.contains(&format!(
+            "Cross-program invocation exceeds max depth of {}",
+            MAX_INVOKE_DEPTH
+        )));
+        assert!(!logs.contains(&format!("Program {} invoke [{}]", recursive_id(), MAX_INVOKE_DEPTH + 1)));
+    }
+}
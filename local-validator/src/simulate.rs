@@ -0,0 +1,399 @@
+use arch_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sanitized::ArchMessage,
+};
+use bitcoin::hashes::{sha256, Hash as _};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::panic;
+
+use crate::bank::{Account, Bank, ProcessInstruction};
+use crate::helper::FAUCET_LAMPORTS;
+
+/// Key name that refers to the program being simulated.
+pub const PROGRAM_KEY: &str = "program";
+/// Key name that refers to the system program.
+pub const SYSTEM_KEY: &str = "system";
+
+/// A transaction described in JSON. Keys are 64-character hex pubkeys or
+/// names; a name stands for a keypair that exists only in the simulation,
+/// so lessons can write `"payer"` or `"mint"` instead of raw keys.
+#[derive(Debug, Deserialize)]
+pub struct SimulationSpec {
+    pub instructions: Vec<InstructionSpec>,
+    /// Keys that sign the transaction; the first one pays. Signatures
+    /// aren't verified, so a pubkey works as well as a named keypair.
+    #[serde(default)]
+    pub signers: Vec<String>,
+    /// Accounts that exist before the transaction, e.g. from a saved ledger.
+    #[serde(default)]
+    pub accounts: Vec<LedgerAccount>,
+}
+
+/// An account with its hex pubkey, as saved between simulations.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LedgerAccount {
+    pub pubkey: String,
+    #[serde(flatten)]
+    pub state: AccountState,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstructionSpec {
+    pub program_id: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountMetaSpec>,
+    #[serde(default)]
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountMetaSpec {
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulationReport {
+    /// Hex id the submission was deployed under.
+    pub program_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+    pub logs: Vec<String>,
+    pub instructions: Vec<InstructionReport>,
+    /// Accounts whose state the transaction changed.
+    pub accounts: Vec<AccountDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InstructionReport {
+    pub index: usize,
+    pub program_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountDiff {
+    pub pubkey: String,
+    /// The name the spec used for this key, if any.
+    pub name: Option<String>,
+    pub before: Option<AccountState>,
+    pub after: AccountState,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+pub struct AccountState {
+    pub lamports: u64,
+    pub owner: String,
+    /// Account data as hex.
+    pub data: String,
+    pub is_executable: bool,
+}
+
+impl From<&Account> for AccountState {
+    fn from(account: &Account) -> Self {
+        AccountState {
+            lamports: account.lamports,
+            owner: pubkey_hex(&account.owner),
+            data: hex::encode(&account.data),
+            is_executable: account.is_executable,
+        }
+    }
+}
+
+impl TryFrom<&AccountState> for Account {
+    type Error = String;
+
+    fn try_from(state: &AccountState) -> Result<Self, Self::Error> {
+        Ok(Account {
+            lamports: state.lamports,
+            data: hex::decode(&state.data).map_err(|e| format!("Invalid account data: {}", e))?,
+            owner: parse_pubkey(&state.owner)?,
+            is_executable: state.is_executable,
+        })
+    }
+}
+
+pub fn pubkey_hex(pubkey: &Pubkey) -> String {
+    hex::encode(pubkey.serialize())
+}
+
+fn parse_pubkey(key: &str) -> Result<Pubkey, String> {
+    let bytes = hex::decode(key).map_err(|e| format!("Invalid pubkey {}: {}", key, e))?;
+    if bytes.len() != 32 {
+        return Err(format!("Invalid pubkey {}: expected 32 bytes", key));
+    }
+    Ok(Pubkey::from_slice(&bytes))
+}
+
+/// Pubkey for a named key: stable across runs so names can be reused.
+pub fn named_pubkey(name: &str) -> Pubkey {
+    let hash = sha256::Hash::hash(format!("bitcoin-zombies:{}", name).as_bytes());
+    Pubkey::from_slice(&hash.to_byte_array())
+}
+
+/// Resolves keys in a spec and remembers which names they came from.
+#[derive(Default)]
+struct KeyResolver {
+    names: HashMap<Pubkey, String>,
+}
+
+impl KeyResolver {
+    fn resolve(&mut self, key: &str) -> Result<Pubkey, String> {
+        if key == SYSTEM_KEY {
+            return Ok(Pubkey::system_program());
+        }
+        if key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit()) {
+            return parse_pubkey(key);
+        }
+        if key.is_empty() {
+            return Err("Empty key name".to_string());
+        }
+        let pubkey = named_pubkey(key);
+        self.names.insert(pubkey, key.to_string());
+        Ok(pubkey)
+    }
+}
+
+/// Simulates a transaction against a bank without committing it. Signers
+/// that don't exist yet are funded from the faucet first, so a fresh named
+/// payer can create accounts.
+pub fn simulate(bank: &mut Bank, spec: &SimulationSpec) -> Result<SimulationReport, String> {
+    if spec.instructions.is_empty() {
+        return Err("A transaction needs at least one instruction".to_string());
+    }
+
+    for account in &spec.accounts {
+        bank.set_account(&parse_pubkey(&account.pubkey)?, Account::try_from(&account.state)?);
+    }
+
+    let mut keys = KeyResolver::default();
+    let signers = spec
+        .signers
+        .iter()
+        .map(|signer| keys.resolve(signer))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut instructions = Vec::new();
+    for (index, instruction) in spec.instructions.iter().enumerate() {
+        let mut accounts = Vec::new();
+        for meta in &instruction.accounts {
+            let pubkey = keys.resolve(&meta.pubkey)?;
+            if meta.is_signer && !signers.contains(&pubkey) {
+                return Err(format!(
+                    "Instruction {} needs `{}` to sign, but it isn't in `signers`",
+                    index, meta.pubkey
+                ));
+            }
+            accounts.push(AccountMeta {
+                pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            });
+        }
+        instructions.push(Instruction {
+            program_id: keys.resolve(&instruction.program_id)?,
+            accounts,
+            data: instruction.data.clone(),
+        });
+    }
+
+    for signer in &signers {
+        if bank.get_account(signer).is_none() {
+            bank.airdrop(signer, FAUCET_LAMPORTS);
+        }
+    }
+
+    let message = ArchMessage::new(
+        &instructions,
+        signers.first().copied(),
+        bank.best_block_hash(),
+    );
+    let mut logs = Vec::new();
+    let outcome = bank.simulate_message(&message, &mut logs)?;
+
+    let error = outcome
+        .error()
+        .map(|(index, err)| format!("Instruction {} failed: {}", index, err));
+    let instructions = outcome
+        .instructions
+        .iter()
+        .enumerate()
+        .map(|(index, instruction)| InstructionReport {
+            index,
+            program_id: pubkey_hex(&instruction.program_id),
+            success: instruction.result.is_ok(),
+            error: instruction.result.clone().err(),
+        })
+        .collect();
+
+    // Failed transactions don't commit, so only successful ones have diffs.
+    // A missing account loads as an empty system account, so one that's
+    // still empty afterwards was only referenced, not created
+    let empty = AccountState::from(&Account::new(0, 0, &Pubkey::system_program()));
+    let mut accounts = Vec::new();
+    if error.is_none() {
+        for (pubkey, account) in &outcome.accounts {
+            let before = bank.get_account(pubkey).map(AccountState::from);
+            let after = AccountState::from(account);
+            if before.as_ref().unwrap_or(&empty) != &after {
+                accounts.push(AccountDiff {
+                    pubkey: pubkey_hex(pubkey),
+                    name: keys.names.get(pubkey).cloned(),
+                    before,
+                    after,
+                });
+            }
+        }
+    }
+
+    Ok(SimulationReport {
+        program_id: pubkey_hex(&named_pubkey(PROGRAM_KEY)),
+        success: error.is_none(),
+        error,
+        logs,
+        instructions,
+        accounts,
+    })
+}
+
+/// Entry point for the runner's simulation harness: deploys the submission
+/// as `program` in a fresh bank, simulates the JSON spec and returns the
+/// report as JSON. Even a panic outside the program comes back as a report,
+/// so the caller always gets an error it can show.
+pub fn run(spec: &str, entrypoint: ProcessInstruction) -> String {
+    let report = serde_json::from_str::<SimulationSpec>(spec)
+        .map_err(|e| format!("Invalid simulation spec: {}", e))
+        .and_then(|spec| {
+            panic::catch_unwind(|| {
+                let mut bank = Bank::new();
+                bank.add_program(&named_pubkey(PROGRAM_KEY), entrypoint);
+                simulate(&mut bank, &spec)
+            })
+            .unwrap_or_else(|_| Err("The local validator panicked".to_string()))
+        });
+
+    match report {
+        Ok(report) => serde_json::to_string(&report).unwrap_or_default(),
+        Err(err) => serde_json::json!({ "success": false, "error": err }).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arch_program::{account::AccountInfo, entrypoint::ProgramResult, system_instruction};
+    use serde_json::json;
+
+    fn noop(_program_id: &Pubkey, _accounts: &[AccountInfo], _data: &[u8]) -> ProgramResult {
+        Ok(())
+    }
+
+    fn transfer_data(lamports: u64) -> Vec<u8> {
+        system_instruction::transfer(&Pubkey::system_program(), &Pubkey::system_program(), lamports)
+            .data
+    }
+
+    fn spec(value: serde_json::Value) -> SimulationSpec {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn diffs_cover_only_changed_accounts() {
+        let mut bank = Bank::new();
+        // `bystander` is referenced but never touched, so it stays absent
+        let report = simulate(
+            &mut bank,
+            &spec(json!({
+                "signers": ["payer"],
+                "instructions": [{
+                    "program_id": "system",
+                    "accounts": [
+                        { "pubkey": "payer", "is_signer": true, "is_writable": true },
+                        { "pubkey": "recipient", "is_writable": true },
+                        { "pubkey": "bystander" },
+                    ],
+                    "data": transfer_data(100),
+                }],
+            })),
+        )
+        .unwrap();
+
+        assert!(report.success, "{:?}", report.error);
+        let mut names: Vec<_> = report
+            .accounts
+            .iter()
+            .map(|diff| diff.name.as_deref().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["payer", "recipient"]);
+
+        let recipient = report
+            .accounts
+            .iter()
+            .find(|diff| diff.name.as_deref() == Some("recipient"))
+            .unwrap();
+        assert_eq!(recipient.before, None);
+        assert_eq!(recipient.after.lamports, 100);
+    }
+
+    #[test]
+    fn failed_transactions_have_no_diffs() {
+        let mut bank = Bank::new();
+        let report = simulate(
+            &mut bank,
+            &spec(json!({
+                "signers": ["payer"],
+                "instructions": [{
+                    "program_id": "system",
+                    "accounts": [
+                        { "pubkey": "payer", "is_signer": true, "is_writable": true },
+                        { "pubkey": "recipient", "is_writable": true },
+                    ],
+                    "data": transfer_data(FAUCET_LAMPORTS + 1),
+                }],
+            })),
+        )
+        .unwrap();
+
+        assert!(!report.success);
+        assert!(report.error.unwrap().starts_with("Instruction 0 failed"));
+        assert!(report.accounts.is_empty());
+    }
+
+    #[test]
+    fn signers_must_be_listed() {
+        let mut bank = Bank::new();
+        let err = simulate(
+            &mut bank,
+            &spec(json!({
+                "instructions": [{
+                    "program_id": "program",
+                    "accounts": [{ "pubkey": "payer", "is_signer": true }],
+                }],
+            })),
+        )
+        .unwrap_err();
+        assert!(err.contains("isn't in `signers`"));
+    }
+
+    #[test]
+    fn run_reports_errors_as_json() {
+        let report: serde_json::Value =
+            serde_json::from_str(&run("{ not json", noop)).unwrap();
+        assert_eq!(report["success"], json!(false));
+        assert!(report["error"].as_str().unwrap().starts_with("Invalid simulation spec"));
+
+        let report: serde_json::Value = serde_json::from_str(&run(
+            r#"{ "instructions": [{ "program_id": "program" }] }"#,
+            noop,
+        ))
+        .unwrap();
+        assert_eq!(report["success"], json!(true));
+    }
+}
@@ -0,0 +1,46 @@
+use arch_program::{
+    account::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+};
+use std::cell::RefCell;
+use std::sync::Once;
+
+thread_local! {
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Routes `msg!` from natively compiled programs into the bank's log buffer
+/// instead of stdout, and `invoke` into the local runtime.
+struct LocalSyscalls;
+
+impl SyscallStubs for LocalSyscalls {
+    fn sol_log(&self, message: &str) {
+        push_log(format!("Program log: {}", message));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        crate::runtime::invoke_signed(instruction, account_infos, signers_seeds)
+    }
+}
+
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        set_syscall_stubs(Box::new(LocalSyscalls));
+    });
+}
+
+pub fn push_log(line: String) {
+    LOGS.with(|logs| logs.borrow_mut().push(line));
+}
+
+pub fn take_logs() -> Vec<String> {
+    LOGS.with(|logs| std::mem::take(&mut *logs.borrow_mut()))
+}
@@ -0,0 +1,107 @@
+use arch_program::{
+    account::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    pubkey::Pubkey, system_instruction::SystemInstruction,
+};
+
+/// Largest account the system program will allocate, as on-chain.
+pub const MAX_PERMITTED_DATA_LENGTH: u64 = 10 * 1024 * 1024;
+
+/// Native implementation of the instructions lesson tests rely on:
+/// creating, assigning and allocating accounts and moving lamports.
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction: SystemInstruction =
+        bincode::deserialize(instruction_data).map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    match instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let from = account_at(accounts, 0)?;
+            let to = account_at(accounts, 1)?;
+            if !to.is_signer {
+                msg!("Create Account: account {} must sign", to.key);
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if to.lamports() > 0 || to.data_len() > 0 || *to.owner != Pubkey::system_program() {
+                msg!("Create Account: account {} already in use", to.key);
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+            allocate(to, space)?;
+            assign(to, &owner)?;
+            transfer(from, to, lamports)
+        }
+        SystemInstruction::Assign { owner } => assign(account_at(accounts, 0)?, &owner),
+        SystemInstruction::Transfer { lamports } => {
+            transfer(account_at(accounts, 0)?, account_at(accounts, 1)?, lamports)
+        }
+        SystemInstruction::Allocate { space } => allocate(account_at(accounts, 0)?, space),
+        #[allow(unreachable_patterns)]
+        _ => {
+            msg!("Instruction not supported by the local system program");
+            Err(ProgramError::InvalidInstructionData)
+        }
+    }
+}
+
+fn account_at<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+    index: usize,
+) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+fn allocate(account: &AccountInfo, space: u64) -> ProgramResult {
+    if !account.is_signer {
+        msg!("Allocate: account {} must sign", account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if account.data_len() > 0 {
+        msg!("Allocate: account {} already has data", account.key);
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        msg!("Allocate: requested {} bytes, max {}", space, MAX_PERMITTED_DATA_LENGTH);
+        return Err(ProgramError::InvalidArgument);
+    }
+    account.realloc(space as usize, true)
+}
+
+fn assign(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner == owner {
+        return Ok(());
+    }
+    if !account.is_signer {
+        msg!("Assign: account {} must sign", account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    account.assign(owner);
+    Ok(())
+}
+
+fn transfer(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+    if !from.is_signer {
+        msg!("Transfer: `from` account {} must sign", from.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if from.data_len() > 0 {
+        msg!("Transfer: `from` must not carry data");
+        return Err(ProgramError::InvalidArgument);
+    }
+    let from_lamports = from
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(ProgramError::InsufficientFunds)?;
+    let to_lamports = to
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **from.lamports.borrow_mut() = from_lamports;
+    **to.lamports.borrow_mut() = to_lamports;
+    Ok(())
+}